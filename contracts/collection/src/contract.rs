@@ -1,32 +1,33 @@
 #[cfg(not(feature = "library"))]
 use crate::ContractError;
-use crate::state::{Config, CONFIG, SALE};
+use crate::state::{Config, CONFIG, SALE, SUPPORTED_DENOMS, MINT_PHASE_COUNT, MINT_PHASES, MINTED, PAYOUT_ADDRESS, AIRDROP_COUNT, AIRDROP, AIRDROP_CLAIMED, SHUFFLE_COMMIT, SHUFFLE_SEED, SHUFFLE_POOL, SHUFFLE_SWAP, REVEAL, UNREVEALED, MINTED_SUPPLY, ESCROW, TRADE_COUNT, TRADES, TOKEN_TRADES, ACCOUNT_TRADES, PAUSED, CW721_WHITELIST, CW721_TRADES, ESCROW_REBUILD};
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
     to_binary, Addr, Binary, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Reply, ReplyOn, Response, Api,
-    StdResult, SubMsg, Uint128, WasmMsg, Coin, from_binary, BankMsg, QueryRequest, WasmQuery, Storage, Order
+    StdError, StdResult, SubMsg, Uint128, WasmMsg, Coin, from_binary, BankMsg, QueryRequest, WasmQuery, Storage, Order, Event, QuerierWrapper
 };
 use cw2::set_contract_version;
 use cw721::{
-    OwnerOfResponse,
+    OwnerOfResponse, NftInfoResponse, NumTokensResponse,
 
 };
 use cw20::Denom;
 
-use crate::constants::{ATOMPOOL, OSMOPOOL, USDCPOOL, SCRTPOOL, BLOCKATOMPOOL, BLOCKJUNOPOOL, BLOCKMARBLEPOOL, ATOMDENOM, OSMODENOM, USDCDENOM, SCRTDENOM, JUNODENOM, BLOCKADDR, MARBLEADDR};
 use cw2::{get_contract_version};
 use cw721::Cw721ReceiveMsg;
 use cw_storage_plus::Bound;
 use cw721_base::{
     msg::ExecuteMsg as Cw721ExecuteMsg, msg::InstantiateMsg as Cw721InstantiateMsg, Extension,
-    msg::MintMsg, msg::BatchMintMsg, msg::QueryMsg as Cw721QueryMsg,  msg::EditMsg
+    msg::MintMsg, msg::BatchMintMsg, msg::QueryMsg as Cw721QueryMsg,  msg::EditMsg,
+    msg::FrozenResponse, msg::ProvenanceResponse, TransferPolicy
 };
-use crate::msg::{ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg, ReceiveMsg, MigrateMsg, SaleType, DurationType, SaleInfo, SalesResponse, Request, NftReceiveMsg};
+use crate::msg::{ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg, ReceiveMsg, MigrateMsg, SaleType, DurationType, SaleInfo, SalesResponse, Request, NftReceiveMsg, SupportedDenom, SupportedDenomsResponse, MintPhase, MintPhaseInfo, MintPhasesResponse, PhaseMinted, MintedByResponse, Airdrop, AirdropResponse, IsClaimedResponse, RevealInfo, SupplyResponse, SaleItem, SaleEdit, SweepResult, SweepItem, SweepResponse, EscrowBalancesResponse, EscrowCheck, VerifyEscrowResponse, Trade, TradesResponse, DenomTradeStats, TradeStatsResponse, PauseScope, PausedResponse, Role, HasRoleResponse, RoyaltySource, WhitelistedCw721, Cw721WhitelistResponse, Cw2981QueryMsg, Cw2981Query, RoyaltiesInfoResponse};
 use cw_utils::{Expiration, Scheduled};
-use cw20::{Cw20ReceiveMsg, Cw20ExecuteMsg, Cw20CoinVerified, Balance};
+use cw20::{Cw20ReceiveMsg, Cw20CoinVerified, Balance};
 use cw_utils::parse_reply_instantiate_data;
 use sha2::Digest;
 use std::convert::TryInto;
+use std::collections::BTreeMap;
 
 use crate::util;
 
@@ -34,6 +35,8 @@ use crate::util;
 const CONTRACT_NAME: &str = "marble-collection";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 const INSTANTIATE_TOKEN_REPLY_ID: u64 = 1;
+const MAX_BATCH_SIZE: usize = 30;
+const MAX_SALE_EDITS: usize = 10;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -76,6 +79,10 @@ pub fn instantiate(
 
     CONFIG.save(deps.storage, &config)?;
 
+    if let Some(shuffle_commit) = msg.shuffle_commit {
+        SHUFFLE_COMMIT.save(deps.storage, &util::decode_hash(&shuffle_commit)?)?;
+    }
+
     let sub_msg: Vec<SubMsg> = vec![SubMsg {
         msg: WasmMsg::Instantiate {
             code_id: msg.token_code_id,
@@ -83,6 +90,8 @@ pub fn instantiate(
                 name: msg.name.clone() + " cw721_base",
                 symbol: msg.symbol,
                 minter: env.contract.address.to_string(),
+                soulbound: false,
+                index_traits: msg.index_traits,
             })?,
             funds: vec![],
             admin: None,
@@ -118,11 +127,29 @@ pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, crate::Co
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::GetConfig {} => to_binary(&query_config(deps)?),
-        QueryMsg::GetSale {token_id} => to_binary(&query_get_sale(deps, token_id)?),
-        QueryMsg::GetSales {start_after, limit} => to_binary(&query_get_sales(deps, start_after, limit)?),
+        QueryMsg::GetSale {cw721_address, token_id} => to_binary(&query_get_sale(deps, cw721_address, token_id)?),
+        QueryMsg::GetSales {cw721_address, start_after, limit} => to_binary(&query_get_sales(deps, cw721_address, start_after, limit)?),
+        QueryMsg::GetSupportedDenom {denom} => to_binary(&query_get_supported_denom(deps, denom)?),
+        QueryMsg::GetSupportedDenoms {start_after, limit} => to_binary(&query_get_supported_denoms(deps, start_after, limit)?),
+        QueryMsg::MintPhases {} => to_binary(&query_mint_phases(deps)?),
+        QueryMsg::MintedBy {address} => to_binary(&query_minted_by(deps, address)?),
+        QueryMsg::Airdrop {} => to_binary(&query_airdrop(deps)?),
+        QueryMsg::IsClaimed {index} => to_binary(&query_is_claimed(deps, index)?),
+        QueryMsg::RevealInfo {} => to_binary(&REVEAL.may_load(deps.storage)?),
+        QueryMsg::MetadataFrozen {} => to_binary(&query_metadata_frozen(deps)?),
+        QueryMsg::Provenance {} => to_binary(&query_provenance(deps)?),
+        QueryMsg::Supply {} => to_binary(&query_supply(deps)?),
+        QueryMsg::EscrowBalances {} => to_binary(&query_escrow_balances(deps)?),
+        QueryMsg::VerifyEscrow {} => to_binary(&query_verify_escrow(deps, env)?),
+        QueryMsg::TradesByToken {cw721_address, token_id, start_after, limit} => to_binary(&query_trades_by_token(deps, cw721_address, token_id, start_after, limit)?),
+        QueryMsg::TradesByAccount {address, start_after, limit} => to_binary(&query_trades_by_account(deps, address, start_after, limit)?),
+        QueryMsg::TradeStats {cw721_address, window} => to_binary(&query_trade_stats(deps, cw721_address, window)?),
+        QueryMsg::Cw721Whitelist {start_after, limit} => to_binary(&query_cw721_whitelist(deps, start_after, limit)?),
+        QueryMsg::Paused {} => to_binary(&query_paused(deps)?),
+        QueryMsg::HasRole {role, address} => to_binary(&HasRoleResponse { has_role: marble_roles::has_role(deps.storage, &CONFIG, &address, &role)? }),
     }
 }
 
@@ -138,21 +165,33 @@ fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
         maximum_royalty_fee: config.maximum_royalty_fee,
         royalties: config.royalties,
         uri: config.uri,
-        enabled: config.enabled
+        enabled: config.enabled,
+        shuffle: SHUFFLE_COMMIT.may_load(deps.storage)?.is_some()
     })
 }
 
 
+fn query_cw721_address(
+    deps: Deps,
+    cw721_address: Option<Addr>
+) -> StdResult<Addr> {
+    util::resolve_cw721_address(deps.storage, cw721_address)
+        .map_err(|err| StdError::generic_err(err.to_string()))
+}
+
 fn query_get_sale(
     deps: Deps,
-    token_id: u32,
+    cw721_address: Option<Addr>,
+    token_id: String,
 ) -> StdResult<SaleInfo> {
-
-    let sale_info = SALE.load(deps.storage, token_id.to_string())?;
+    let cw721_address = query_cw721_address(deps, cw721_address)?;
+    let sale_info = SALE.load(deps.storage, (&cw721_address, token_id))?;
     Ok(sale_info)
 }
 const MAX_LIMIT: u32 = 30;
 const DEFAULT_LIMIT: u32 = 20;
+const DEFAULT_STATS_WINDOW: u32 = 50;
+const MAX_STATS_WINDOW: u32 = 200;
 
 
 fn map_sales(
@@ -165,15 +204,18 @@ fn map_sales(
 
 fn query_get_sales(
     deps: Deps,
-    start_after: Option<u32>,
+    cw721_address: Option<Addr>,
+    start_after: Option<String>,
     limit: Option<u32>
 ) -> StdResult<SalesResponse> {
+    let cw721_address = query_cw721_address(deps, cw721_address)?;
 
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
 
     let start = start_after.map(|str| Bound::exclusive(str.to_string()));
 
     let sales:StdResult<Vec<_>> = SALE
+        .prefix(&cw721_address)
         .range(deps.storage, start, None, Order::Ascending)
         .take(limit)
         .map(|item| map_sales(item))
@@ -185,6 +227,293 @@ fn query_get_sales(
 
 }
 
+fn query_get_supported_denom(
+    deps: Deps,
+    denom: Denom
+) -> StdResult<SupportedDenom> {
+    SUPPORTED_DENOMS.load(deps.storage, util::denom_key(&denom))
+}
+
+fn query_get_supported_denoms(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>
+) -> StdResult<SupportedDenomsResponse> {
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let start = start_after.map(Bound::exclusive);
+
+    let list:StdResult<Vec<_>> = SUPPORTED_DENOMS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_key, record)| record))
+        .collect();
+
+    Ok(SupportedDenomsResponse {
+        list: list?
+    })
+}
+
+fn query_mint_phases(
+    deps: Deps
+) -> StdResult<MintPhasesResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let list:StdResult<Vec<_>> = MINT_PHASES
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(phase_id, phase)| MintPhaseInfo { phase_id, phase }))
+        .collect();
+
+    Ok(MintPhasesResponse {
+        payout_address: PAYOUT_ADDRESS.may_load(deps.storage)?.unwrap_or(config.owner),
+        list: list?
+    })
+}
+
+fn query_minted_by(
+    deps: Deps,
+    address: Addr
+) -> StdResult<MintedByResponse> {
+    let mut list: Vec<PhaseMinted> = vec![];
+    let mut total = 0u32;
+    for phase_id in MINT_PHASES.keys(deps.storage, None, None, Order::Ascending) {
+        let phase_id = phase_id?;
+        let count = MINTED.may_load(deps.storage, (phase_id, &address))?.unwrap_or_default();
+        if count > 0 {
+            total += count;
+            list.push(PhaseMinted { phase_id, count });
+        }
+    }
+
+    Ok(MintedByResponse {
+        address,
+        total,
+        list
+    })
+}
+
+fn query_airdrop(
+    deps: Deps
+) -> StdResult<AirdropResponse> {
+    Ok(AirdropResponse {
+        airdrop_id: AIRDROP_COUNT.may_load(deps.storage)?.unwrap_or_default(),
+        airdrop: AIRDROP.may_load(deps.storage)?
+    })
+}
+
+fn query_is_claimed(
+    deps: Deps,
+    index: u32
+) -> StdResult<IsClaimedResponse> {
+    let airdrop_id = AIRDROP_COUNT.may_load(deps.storage)?.unwrap_or_default();
+    Ok(IsClaimedResponse {
+        index,
+        is_claimed: is_airdrop_claimed(deps.storage, airdrop_id, index)?
+    })
+}
+
+fn query_metadata_frozen(
+    deps: Deps
+) -> StdResult<FrozenResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    match config.cw721_address {
+        Some(cw721_address) => deps.querier.query_wasm_smart(cw721_address, &Cw721QueryMsg::MetadataFrozen {}),
+        None => Ok(FrozenResponse { frozen: false })
+    }
+}
+
+fn query_provenance(
+    deps: Deps
+) -> StdResult<ProvenanceResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    match config.cw721_address {
+        Some(cw721_address) => deps.querier.query_wasm_smart(cw721_address, &Cw721QueryMsg::Provenance {}),
+        None => Ok(ProvenanceResponse { provenance_hash: None })
+    }
+}
+
+fn query_supply(
+    deps: Deps
+) -> StdResult<SupplyResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let minted = util::minted_supply(deps.storage, &config)?;
+
+    // anyone holding a token can burn it on the cw721 contract directly
+    let burned = match config.cw721_address.clone() {
+        Some(cw721_address) => {
+            let res: NumTokensResponse = deps.querier.query_wasm_smart(cw721_address, &Cw721QueryMsg::NumTokens {})?;
+            minted.saturating_sub(res.count as u32)
+        },
+        None => 0
+    };
+
+    Ok(SupplyResponse {
+        max_tokens: config.max_tokens,
+        minted,
+        burned,
+        remaining: util::remaining_tokens(&config)
+    })
+}
+
+fn query_escrow_balances(
+    deps: Deps
+) -> StdResult<EscrowBalancesResponse> {
+    let list:StdResult<Vec<_>> = ESCROW
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_key, record)| record))
+        .collect();
+
+    Ok(EscrowBalancesResponse {
+        list: list?
+    })
+}
+
+fn query_verify_escrow(
+    deps: Deps,
+    env: Env
+) -> StdResult<VerifyEscrowResponse> {
+    let mut solvent = true;
+    let mut list: Vec<EscrowCheck> = vec![];
+    for escrow in query_escrow_balances(deps)?.list {
+        let balance = util::get_token_amount(deps.querier, escrow.denom.clone(), env.contract.address.clone())
+            .map_err(|err| StdError::generic_err(err.to_string()))?;
+        solvent = solvent && balance >= escrow.amount;
+        list.push(EscrowCheck {
+            denom: escrow.denom,
+            escrow: escrow.amount,
+            balance
+        });
+    }
+
+    Ok(VerifyEscrowResponse {
+        solvent,
+        rebuilding: ESCROW_REBUILD.may_load(deps.storage)?.is_some(),
+        list
+    })
+}
+
+fn load_trades(
+    deps: Deps,
+    ids: Vec<u64>
+) -> StdResult<TradesResponse> {
+    let list:StdResult<Vec<_>> = ids.into_iter()
+        .map(|id| TRADES.load(deps.storage, id))
+        .collect();
+
+    Ok(TradesResponse {
+        list: list?
+    })
+}
+
+fn query_trades_by_token(
+    deps: Deps,
+    cw721_address: Option<Addr>,
+    token_id: String,
+    start_after: Option<u64>,
+    limit: Option<u32>
+) -> StdResult<TradesResponse> {
+    let cw721_address = query_cw721_address(deps, cw721_address)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive_int);
+
+    let ids:StdResult<Vec<_>> = TOKEN_TRADES
+        .prefix((&cw721_address, token_id))
+        .keys(deps.storage, None, start, Order::Descending)
+        .take(limit)
+        .collect();
+
+    load_trades(deps, ids?)
+}
+
+fn query_trades_by_account(
+    deps: Deps,
+    address: Addr,
+    start_after: Option<u64>,
+    limit: Option<u32>
+) -> StdResult<TradesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive_int);
+
+    let ids:StdResult<Vec<_>> = ACCOUNT_TRADES
+        .prefix(&address)
+        .keys(deps.storage, None, start, Order::Descending)
+        .take(limit)
+        .collect();
+
+    load_trades(deps, ids?)
+}
+
+fn query_trade_stats(
+    deps: Deps,
+    cw721_address: Option<Addr>,
+    window: Option<u32>
+) -> StdResult<TradeStatsResponse> {
+    let window = window.unwrap_or(DEFAULT_STATS_WINDOW).min(MAX_STATS_WINDOW) as usize;
+
+    let trades:StdResult<Vec<_>> = match cw721_address {
+        Some(cw721_address) => CW721_TRADES
+            .prefix(&cw721_address)
+            .keys(deps.storage, None, None, Order::Descending)
+            .take(window)
+            .map(|id| TRADES.load(deps.storage, id?))
+            .collect(),
+        None => TRADES
+            .range(deps.storage, None, None, Order::Descending)
+            .take(window)
+            .map(|item| item.map(|(_key, trade)| trade))
+            .collect()
+    };
+    let trades = trades?;
+
+    // trades come newest first, so the first one seen for a denom holds its last price
+    let mut stats: BTreeMap<String, DenomTradeStats> = BTreeMap::new();
+    for trade in trades.iter() {
+        let entry = stats.entry(util::denom_key(&trade.denom)).or_insert(DenomTradeStats {
+            denom: trade.denom.clone(),
+            trades: 0,
+            volume: Uint128::zero(),
+            last_price: trade.price,
+            floor_price: trade.price
+        });
+        entry.trades += 1;
+        entry.volume += trade.price;
+        entry.floor_price = entry.floor_price.min(trade.price);
+    }
+
+    Ok(TradeStatsResponse {
+        last_trade: trades.first().cloned(),
+        list: stats.into_values().collect()
+    })
+}
+
+fn query_cw721_whitelist(
+    deps: Deps,
+    start_after: Option<Addr>,
+    limit: Option<u32>
+) -> StdResult<Cw721WhitelistResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|address| Bound::exclusive(address.as_str()));
+
+    let list:StdResult<Vec<_>> = CW721_WHITELIST
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(cw721_address, royalty)| WhitelistedCw721 { cw721_address, royalty }))
+        .collect();
+
+    Ok(Cw721WhitelistResponse {
+        list: list?
+    })
+}
+
+fn query_paused(
+    deps: Deps
+) -> StdResult<PausedResponse> {
+    Ok(PausedResponse {
+        enabled: CONFIG.load(deps.storage)?.enabled,
+        list: PAUSED.may_load(deps.storage)?.unwrap_or_default()
+    })
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
@@ -195,22 +524,32 @@ pub fn execute(
     match msg {
         ExecuteMsg::UpdateOwner { owner } => util::execute_update_owner(deps.storage, info.sender, owner),
         ExecuteMsg::UpdateEnabled { enabled } => util::execute_update_enabled(deps.storage, info.sender, enabled),
+        ExecuteMsg::GrantRole { role, address } => Ok(marble_roles::execute_grant_role(deps.storage, &CONFIG, &info.sender, role, address)?),
+        ExecuteMsg::RevokeRole { role, address } => Ok(marble_roles::execute_revoke_role(deps.storage, &CONFIG, &info.sender, role, address)?),
+        ExecuteMsg::SetPaused { scope, paused } => util::execute_set_paused(deps.storage, info.sender, scope, paused),
+        ExecuteMsg::UnwindAllSales { limit } => execute_unwind_all_sales(deps, info, limit),
+        ExecuteMsg::RebuildEscrow { limit } => execute_rebuild_escrow(deps, info, limit),
         ExecuteMsg::UpdateRoyalties { maximum_royalty_fee, royalties } => util::execute_update_royalties(deps.storage, info.sender, maximum_royalty_fee, royalties),
         ExecuteMsg::ReceiveNft(msg) => execute_receive_nft(deps, info, msg),
-        ExecuteMsg::AcceptSale { token_id } => {
-            execute_accept_sale(deps, info, token_id)
+        ExecuteMsg::AcceptSale { cw721_address, token_id } => {
+            let cw721_address = util::resolve_cw721_address(deps.storage, cw721_address)?;
+            execute_accept_sale(deps, env, info, cw721_address, token_id)
         },
-        ExecuteMsg::CancelSale { token_id } => {
-            execute_cancel_sale(deps, info, token_id)
+        ExecuteMsg::CancelSale { cw721_address, token_id } => {
+            let cw721_address = util::resolve_cw721_address(deps.storage, cw721_address)?;
+            execute_cancel_sale(deps, info, cw721_address, token_id)
         },
-        ExecuteMsg::Mint{ uri, extension } => {
-            execute_mint(deps, env, info, uri, extension)
+        ExecuteMsg::Mint{ uri, extension, proof } => {
+            execute_mint(deps, env, info.sender.clone(), Balance::from(info.funds), uri, extension, proof)
         },
         ExecuteMsg::BatchMint{ uri, extension, owner} => {
-            execute_batch_mint(deps, env, info, uri, extension, owner)
+            execute_batch_mint(deps, info, uri, extension, owner)
         },
 
-        ExecuteMsg::Propose{token_id, denom} => execute_propose(deps, env, info, token_id, denom),
+        ExecuteMsg::Propose{cw721_address, token_id, denom, min_output} => {
+            let cw721_address = util::resolve_cw721_address(deps.storage, cw721_address)?;
+            execute_propose(deps, env, info, cw721_address, token_id, denom, min_output)
+        },
         ExecuteMsg::Receive(msg) => execute_receive(deps, env, info, msg),
 
 
@@ -220,18 +559,63 @@ pub fn execute(
         ExecuteMsg::ChangeCw721Owner {       //Change the owner of Cw721 contract
             owner
         } => execute_change_cw721_owner(deps, info, owner),
+        ExecuteMsg::SetTransferPolicy { policy } => execute_set_transfer_policy(deps, info, policy),
         ExecuteMsg::UpdateUnusedTokenId {
             token_id
         } => execute_update_unused_token_id(deps, info, token_id),
         ExecuteMsg::EditSale {
+            cw721_address,
             token_id,
             sale_type,
             duration_type,
             initial_price,
             reserve_price,
             denom
-        } => execute_edit_sale(deps, info, token_id, sale_type, duration_type, initial_price, reserve_price, denom),
-        ExecuteMsg::CancelPropose { token_id } => execute_cancel_propose(deps, info, token_id)
+        } => execute_edit_sale(deps, env, info, SaleItem {
+            cw721_address,
+            token_id,
+            sale_type,
+            duration_type,
+            initial_price,
+            reserve_price,
+            denom
+        }),
+        ExecuteMsg::BatchStartSale { items } => execute_batch_start_sale(deps, env, info, items),
+        ExecuteMsg::BatchCancelSale { cw721_address, token_ids } => {
+            let cw721_address = util::resolve_cw721_address(deps.storage, cw721_address)?;
+            execute_batch_cancel_sale(deps, info, cw721_address, token_ids)
+        },
+        ExecuteMsg::BatchEditSale { items } => execute_batch_edit_sale(deps, env, info, items),
+        ExecuteMsg::SweepBuy { cw721_address, token_ids } => {
+            let cw721_address = util::resolve_cw721_address(deps.storage, cw721_address)?;
+            execute_sweep_buy(deps, env, info, cw721_address, token_ids)
+        },
+        ExecuteMsg::Sweep { cw721_address, token_ids, max_total } => {
+            let cw721_address = util::resolve_cw721_address(deps.storage, cw721_address)?;
+            execute_sweep(deps, env, info.sender.clone(), Balance::from(info.funds), cw721_address, token_ids, max_total)
+        },
+        ExecuteMsg::CancelPropose { cw721_address, token_id, bid_id } => {
+            let cw721_address = util::resolve_cw721_address(deps.storage, cw721_address)?;
+            execute_cancel_propose(deps, env, info, cw721_address, token_id, bid_id)
+        },
+        ExecuteMsg::AddSupportedDenom { denom, pool_address, decimals, symbol } => execute_add_supported_denom(deps, info, denom, pool_address, decimals, symbol),
+        ExecuteMsg::UpdateSupportedDenom { denom, pool_address, decimals, symbol, enabled } => execute_update_supported_denom(deps, info, SupportedDenom { denom, pool_address, decimals, symbol, enabled }),
+        ExecuteMsg::RemoveSupportedDenom { denom } => execute_remove_supported_denom(deps, info, denom),
+        ExecuteMsg::WhitelistCw721 { cw721_address, royalty } => execute_whitelist_cw721(deps, info, cw721_address, royalty),
+        ExecuteMsg::RemoveCw721 { cw721_address } => execute_remove_cw721(deps, info, cw721_address),
+        ExecuteMsg::AddMintPhase { phase } => execute_add_mint_phase(deps, info, phase),
+        ExecuteMsg::UpdateMintPhase { phase_id, phase } => execute_update_mint_phase(deps, info, phase_id, phase),
+        ExecuteMsg::RemoveMintPhase { phase_id } => execute_remove_mint_phase(deps, info, phase_id),
+        ExecuteMsg::UpdatePayoutAddress { payout_address } => execute_update_payout_address(deps, info, payout_address),
+        ExecuteMsg::EditToken { token_id, uri, extension } => execute_edit_token(deps, info, token_id, uri, extension),
+        ExecuteMsg::SetTokenFrozen { token_id, frozen } => execute_set_token_frozen(deps, info, token_id, frozen),
+        ExecuteMsg::FreezeMetadata { limit } => execute_freeze_metadata(deps, info, limit),
+        ExecuteMsg::SetPlaceholderUri { placeholder_uri } => execute_set_placeholder_uri(deps, info, placeholder_uri),
+        ExecuteMsg::Reveal { base_uri, provenance_hash } => execute_reveal(deps, info, base_uri, provenance_hash),
+        ExecuteMsg::RevealShuffleSeed { seed } => execute_reveal_shuffle_seed(deps, env, info, seed),
+        ExecuteMsg::RevealTokens { limit } => execute_reveal_tokens(deps, limit),
+        ExecuteMsg::RegisterAirdrop { airdrop } => execute_register_airdrop(deps, env, info, airdrop),
+        ExecuteMsg::ClaimAirdrop { index, amount, proof } => execute_claim_airdrop(deps, env, info, index, amount, proof)
 
     }
 }
@@ -240,26 +624,508 @@ pub fn execute(
 pub fn execute_mint(
     deps: DepsMut,
     env: Env,
-    info: MessageInfo,
+    minter: Addr,
+    payment: Balance,
     uri: String,
-    extension: Extension
+    extension: Extension,
+    proof: Option<Vec<String>>
 ) -> Result<Response, crate::ContractError> {
-    util::check_enabled(deps.storage)?;
+    util::check_not_paused(deps.storage, PauseScope::Mint)?;
     let mut config = CONFIG.load(deps.storage)?;
 
     if config.cw721_address == None {
         return Err(crate::ContractError::Uninitialized {});
     }
 
-    if config.unused_token_id >= config.max_tokens {
+    if util::remaining_tokens(&config) == 0 {
         return Err(crate::ContractError::MaxTokensExceed {});
     }
 
+    let (phase_id, phase) = get_active_mint_phase(deps.storage, env.block.time.seconds())?;
+
+    if let Some(merkle_root) = phase.merkle_root.clone() {
+        util::verify_merkle_proof(&merkle_root, minter.as_bytes(), proof.unwrap_or_default())?;
+    }
+
+    let minted = MINTED.may_load(deps.storage, (phase_id, &minter))?.unwrap_or_default() + 1;
+    if let Some(limit) = phase.per_wallet_limit {
+        if minted > limit {
+            return Err(crate::ContractError::WalletLimitExceeded {});
+        }
+    }
+    MINTED.save(deps.storage, (phase_id, &minter), &minted)?;
+
+    let mut msgs: Vec<CosmosMsg> = vec![];
+    if phase.price.is_zero() {
+        if !payment.is_empty() {
+            return Err(crate::ContractError::WrongPaymentAmount {});
+        }
+    } else {
+        // coins of any other denom would stay with the contract
+        if let Balance::Native(coins) = &payment {
+            if coins.0.iter().any(|coin| Denom::Native(coin.denom.clone()) != phase.denom) {
+                return Err(crate::ContractError::WrongPaymentAmount {});
+            }
+        }
+        let amount = util::get_amount_of_denom(payment, phase.denom.clone())?;
+        if amount != phase.price {
+            return Err(crate::ContractError::WrongPaymentAmount {});
+        }
+        let payout_address = PAYOUT_ADDRESS.may_load(deps.storage)?.unwrap_or_else(|| config.owner.clone());
+        msgs.push(util::transfer_token_message(phase.denom.clone(), amount, payout_address)?);
+    }
+
+    let token_id = next_token_id(deps.storage, &mut config)?;
+    let (uri, extension) = token_metadata(deps.storage, token_id, uri, extension)?;
     let mint_msg = Cw721ExecuteMsg::Mint(MintMsg::<Extension> {
-        token_id: config.unused_token_id.to_string(),
-        owner: info.sender.clone().into(),
-        token_uri: uri.clone().into(),
-        extension: extension.clone(),
+        token_id: token_id.to_string(),
+        owner: minter.clone().into(),
+        token_uri: uri.into(),
+        extension,
+        soulbound: phase.soulbound,
+    });
+
+    msgs.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: config.cw721_address.clone().unwrap().to_string(),
+        msg: to_binary(&mint_msg)?,
+        funds: vec![],
+    }));
+
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_attribute("action", "mint")
+        .add_attribute("minter", minter)
+        .add_attribute("token_id", token_id.to_string())
+        .add_attribute("phase_id", phase_id.to_string())
+        .add_attribute("price", phase.price)
+    )
+}
+
+fn get_active_mint_phase(
+    storage: &dyn Storage,
+    now: u64
+) -> Result<(u32, MintPhase), crate::ContractError> {
+    for item in MINT_PHASES.range(storage, None, None, Order::Ascending) {
+        let (phase_id, phase) = item?;
+        if phase.start <= now && now < phase.end {
+            return Ok((phase_id, phase));
+        }
+    }
+    Err(crate::ContractError::NoActiveMintPhase {})
+}
+
+fn validate_mint_phase(
+    storage: &dyn Storage,
+    phase_id: Option<u32>,
+    phase: &MintPhase
+) -> Result<(), crate::ContractError> {
+    if phase.start >= phase.end {
+        return Err(crate::ContractError::DurationIncorrect {});
+    }
+
+    if let Some(merkle_root) = phase.merkle_root.clone() {
+        util::decode_hash(&merkle_root)?;
+    }
+
+    // at most one phase may be active at any time
+    for item in MINT_PHASES.range(storage, None, None, Order::Ascending) {
+        let (id, other) = item?;
+        if Some(id) == phase_id {
+            continue;
+        }
+        if phase.start < other.end && other.start < phase.end {
+            return Err(crate::ContractError::MintPhaseOverlap {});
+        }
+    }
+    Ok(())
+}
+
+pub fn execute_add_mint_phase(
+    deps: DepsMut,
+    info: MessageInfo,
+    phase: MintPhase
+) -> Result<Response, crate::ContractError> {
+    marble_roles::check_role(deps.storage, &CONFIG, &info.sender, Role::Minter)?;
+    validate_mint_phase(deps.storage, None, &phase)?;
+
+    let phase_id = MINT_PHASE_COUNT.may_load(deps.storage)?.unwrap_or_default() + 1;
+    MINT_PHASE_COUNT.save(deps.storage, &phase_id)?;
+    MINT_PHASES.save(deps.storage, phase_id, &phase)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "add_mint_phase")
+        .add_attribute("phase_id", phase_id.to_string())
+    )
+}
+
+pub fn execute_update_mint_phase(
+    deps: DepsMut,
+    info: MessageInfo,
+    phase_id: u32,
+    phase: MintPhase
+) -> Result<Response, crate::ContractError> {
+    marble_roles::check_role(deps.storage, &CONFIG, &info.sender, Role::Minter)?;
+
+    if !MINT_PHASES.has(deps.storage, phase_id) {
+        return Err(crate::ContractError::MintPhaseNotFound {});
+    }
+    validate_mint_phase(deps.storage, Some(phase_id), &phase)?;
+    MINT_PHASES.save(deps.storage, phase_id, &phase)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_mint_phase")
+        .add_attribute("phase_id", phase_id.to_string())
+    )
+}
+
+pub fn execute_remove_mint_phase(
+    deps: DepsMut,
+    info: MessageInfo,
+    phase_id: u32
+) -> Result<Response, crate::ContractError> {
+    marble_roles::check_role(deps.storage, &CONFIG, &info.sender, Role::Minter)?;
+
+    if !MINT_PHASES.has(deps.storage, phase_id) {
+        return Err(crate::ContractError::MintPhaseNotFound {});
+    }
+    MINT_PHASES.remove(deps.storage, phase_id);
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_mint_phase")
+        .add_attribute("phase_id", phase_id.to_string())
+    )
+}
+
+pub fn execute_update_payout_address(
+    deps: DepsMut,
+    info: MessageInfo,
+    payout_address: Addr
+) -> Result<Response, crate::ContractError> {
+    marble_roles::check_role(deps.storage, &CONFIG, &info.sender, Role::RoyaltyManager)?;
+    PAYOUT_ADDRESS.save(deps.storage, &payout_address)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_payout_address")
+        .add_attribute("payout_address", payout_address.to_string())
+    )
+}
+
+pub fn execute_edit_token(
+    deps: DepsMut,
+    info: MessageInfo,
+    token_id: u32,
+    uri: String,
+    extension: Extension
+) -> Result<Response, crate::ContractError> {
+    marble_roles::check_role(deps.storage, &CONFIG, &info.sender, Role::Minter)?;
+    let config = CONFIG.load(deps.storage)?;
+    let cw721_address = config.cw721_address.ok_or(crate::ContractError::Uninitialized {})?;
+
+    let before: NftInfoResponse<Extension> = deps.querier.query_wasm_smart(
+        cw721_address.clone(),
+        &Cw721QueryMsg::NftInfo { token_id: token_id.to_string() }
+    )?;
+    let before_hash = util::metadata_hash(before.token_uri, before.extension)?;
+    let after_hash = util::metadata_hash(Some(uri.clone()), extension.clone())?;
+
+    let edit_msg = CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: cw721_address.to_string(),
+        msg: to_binary(&Cw721ExecuteMsg::Edit(EditMsg::<Extension> {
+            token_id: token_id.to_string(),
+            token_uri: Some(uri),
+            extension,
+        }))?,
+        funds: vec![],
+    });
+
+    Ok(Response::new()
+        .add_message(edit_msg)
+        .add_attribute("action", "edit_token")
+        .add_event(Event::new("edit_token")
+            .add_attribute("token_id", token_id.to_string())
+            .add_attribute("before_hash", before_hash)
+            .add_attribute("after_hash", after_hash)
+        )
+    )
+}
+
+pub fn execute_set_token_frozen(
+    deps: DepsMut,
+    info: MessageInfo,
+    token_id: Option<u32>,
+    frozen: bool
+) -> Result<Response, crate::ContractError> {
+    marble_roles::check_role(deps.storage, &CONFIG, &info.sender, Role::Minter)?;
+    if frozen {
+        util::check_no_pending_reveal(deps.storage, token_id)?;
+    }
+    let config = CONFIG.load(deps.storage)?;
+    let cw721_address = config.cw721_address.ok_or(crate::ContractError::Uninitialized {})?;
+
+    let freeze_msg = CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: cw721_address.to_string(),
+        msg: to_binary(&Cw721ExecuteMsg::<Extension>::SetFrozen {
+            token_id: token_id.map(|token_id| token_id.to_string()),
+            frozen,
+        })?,
+        funds: vec![],
+    });
+
+    Ok(Response::new()
+        .add_message(freeze_msg)
+        .add_attribute("action", "set_token_frozen")
+        .add_attribute("frozen", frozen.to_string())
+    )
+}
+
+pub fn execute_freeze_metadata(
+    deps: DepsMut,
+    info: MessageInfo,
+    limit: Option<u32>
+) -> Result<Response, crate::ContractError> {
+    marble_roles::check_role(deps.storage, &CONFIG, &info.sender, Role::Minter)?;
+    util::check_no_pending_reveal(deps.storage, None)?;
+    let config = CONFIG.load(deps.storage)?;
+    let cw721_address = config.cw721_address.ok_or(crate::ContractError::Uninitialized {})?;
+
+    let freeze_msg = CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: cw721_address.to_string(),
+        msg: to_binary(&Cw721ExecuteMsg::<Extension>::FreezeMetadata { limit })?,
+        funds: vec![],
+    });
+
+    Ok(Response::new()
+        .add_message(freeze_msg)
+        .add_attribute("action", "freeze_metadata")
+    )
+}
+
+pub fn execute_set_placeholder_uri(
+    deps: DepsMut,
+    info: MessageInfo,
+    placeholder_uri: String
+) -> Result<Response, crate::ContractError> {
+    marble_roles::check_role(deps.storage, &CONFIG, &info.sender, Role::Minter)?;
+
+    let reveal = match REVEAL.may_load(deps.storage)? {
+        Some(reveal) => {
+            if reveal.base_uri.is_some() {
+                return Err(crate::ContractError::AlreadyRevealed {});
+            }
+            RevealInfo { placeholder_uri: placeholder_uri.clone(), ..reveal }
+        },
+        None => RevealInfo {
+            placeholder_uri: placeholder_uri.clone(),
+            base_uri: None,
+            provenance_hash: None,
+            unrevealed: 0
+        }
+    };
+    REVEAL.save(deps.storage, &reveal)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_placeholder_uri")
+        .add_attribute("placeholder_uri", placeholder_uri)
+    )
+}
+
+pub fn execute_reveal(
+    deps: DepsMut,
+    info: MessageInfo,
+    base_uri: String,
+    provenance_hash: String
+) -> Result<Response, crate::ContractError> {
+    marble_roles::check_role(deps.storage, &CONFIG, &info.sender, Role::Minter)?;
+    util::decode_hash(&provenance_hash)?;
+
+    let mut reveal = REVEAL.may_load(deps.storage)?.ok_or(crate::ContractError::NotSupported {})?;
+    if reveal.base_uri.is_some() {
+        return Err(crate::ContractError::AlreadyRevealed {});
+    }
+    if SHUFFLE_COMMIT.may_load(deps.storage)?.is_some() && SHUFFLE_SEED.may_load(deps.storage)?.is_none() {
+        return Err(crate::ContractError::ShufflePending {});
+    }
+    reveal.base_uri = Some(base_uri.clone());
+    reveal.provenance_hash = Some(provenance_hash.clone());
+    REVEAL.save(deps.storage, &reveal)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "reveal")
+        .add_attribute("base_uri", base_uri)
+        .add_attribute("provenance_hash", provenance_hash)
+    )
+}
+
+pub fn execute_reveal_shuffle_seed(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    seed: String
+) -> Result<Response, crate::ContractError> {
+    marble_roles::check_role(deps.storage, &CONFIG, &info.sender, Role::Minter)?;
+
+    let commit = SHUFFLE_COMMIT.may_load(deps.storage)?.ok_or(crate::ContractError::NotSupported {})?;
+    if SHUFFLE_SEED.may_load(deps.storage)?.is_some() {
+        return Err(crate::ContractError::AlreadyRevealed {});
+    }
+    // once every id is taken nobody can pick their mint around the outcome
+    let config = CONFIG.load(deps.storage)?;
+    if util::remaining_tokens(&config) > 0 {
+        return Err(crate::ContractError::MintOngoing {});
+    }
+    let hash: [u8; 32] = sha2::Sha256::digest(seed.as_bytes()).into();
+    if hash != commit {
+        return Err(crate::ContractError::VerificationFailed {});
+    }
+
+    // the block mixed in keeps the owner from grinding a seed for a favourable order
+    let mut hasher = sha2::Sha256::new();
+    hasher.update(seed.as_bytes());
+    hasher.update(env.block.height.to_be_bytes());
+    hasher.update(env.block.time.nanos().to_be_bytes());
+    let shuffle_seed: [u8; 32] = hasher.finalize().into();
+    SHUFFLE_SEED.save(deps.storage, &shuffle_seed)?;
+    SHUFFLE_POOL.save(deps.storage, &config.max_tokens)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "reveal_shuffle_seed")
+        .add_attribute("seed", seed)
+    )
+}
+
+pub fn execute_reveal_tokens(
+    deps: DepsMut,
+    limit: Option<u32>
+) -> Result<Response, crate::ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut reveal = REVEAL.may_load(deps.storage)?.ok_or(crate::ContractError::NotRevealed {})?;
+    let base_uri = reveal.base_uri.clone().ok_or(crate::ContractError::NotRevealed {})?;
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let token_ids = UNREVEALED
+        .keys(deps.storage, None, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<u32>>>()?;
+
+    let cw721_address = config.cw721_address.clone().unwrap();
+    let mut msgs: Vec<CosmosMsg> = vec![];
+    let mut burned: u32 = 0;
+    for token_id in token_ids.iter() {
+        UNREVEALED.remove(deps.storage, *token_id);
+        // burned tokens can't be edited, they have nothing left to reveal
+        let owner: StdResult<OwnerOfResponse> = deps.querier.query_wasm_smart(
+            cw721_address.clone(),
+            &Cw721QueryMsg::OwnerOf { token_id: token_id.to_string(), include_expired: None }
+        );
+        if owner.is_err() {
+            burned += 1;
+            continue;
+        }
+        let metadata_id = match SHUFFLE_SEED.may_load(deps.storage)? {
+            Some(_) => draw_metadata_id(deps.storage)?,
+            None => *token_id
+        };
+        msgs.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: cw721_address.to_string(),
+            msg: to_binary(&Cw721ExecuteMsg::Edit(EditMsg::<Extension> {
+                token_id: token_id.to_string(),
+                token_uri: Some(format!("{}{}", base_uri, metadata_id)),
+                extension: None,
+            }))?,
+            funds: vec![],
+        }));
+    }
+    reveal.unrevealed -= token_ids.len() as u32;
+    REVEAL.save(deps.storage, &reveal)?;
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_attribute("action", "reveal_tokens")
+        .add_attribute("count", token_ids.len().to_string())
+        .add_attribute("burned", burned.to_string())
+        .add_attribute("unrevealed", reveal.unrevealed.to_string())
+    )
+}
+
+pub fn execute_register_airdrop(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    airdrop: Airdrop
+) -> Result<Response, crate::ContractError> {
+    marble_roles::check_role(deps.storage, &CONFIG, &info.sender, Role::Minter)?;
+    util::decode_hash(&airdrop.merkle_root)?;
+
+    if airdrop.expiration.is_expired(&env.block) {
+        return Err(crate::ContractError::AlreadyExpired {});
+    }
+
+    // a new id starts a fresh claimed bitmap
+    let airdrop_id = AIRDROP_COUNT.may_load(deps.storage)?.unwrap_or_default() + 1;
+    AIRDROP_COUNT.save(deps.storage, &airdrop_id)?;
+    AIRDROP.save(deps.storage, &airdrop)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "register_airdrop")
+        .add_attribute("airdrop_id", airdrop_id.to_string())
+        .add_attribute("merkle_root", airdrop.merkle_root)
+    )
+}
+
+pub fn execute_claim_airdrop(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    index: u32,
+    amount: u32,
+    proof: Vec<String>
+) -> Result<Response, crate::ContractError> {
+    util::check_not_paused(deps.storage, PauseScope::Mint)?;
+    let mut config = CONFIG.load(deps.storage)?;
+
+    if config.cw721_address.is_none() {
+        return Err(crate::ContractError::Uninitialized {});
+    }
+
+    let airdrop = AIRDROP.may_load(deps.storage)?.ok_or(crate::ContractError::NoAirdrop {})?;
+    if airdrop.expiration.is_expired(&env.block) {
+        return Err(crate::ContractError::AlreadyExpired {});
+    }
+
+    let airdrop_id = AIRDROP_COUNT.load(deps.storage)?;
+    if is_airdrop_claimed(deps.storage, airdrop_id, index)? {
+        return Err(crate::ContractError::Claimed {});
+    }
+
+    let leaf = format!("{}:{}:{}", index, info.sender, amount);
+    util::verify_merkle_proof(&airdrop.merkle_root, leaf.as_bytes(), proof)?;
+
+    if amount > util::remaining_tokens(&config) {
+        return Err(crate::ContractError::MaxTokensExceed {});
+    }
+
+    let word = index / 64;
+    let bits = AIRDROP_CLAIMED.may_load(deps.storage, (airdrop_id, word))?.unwrap_or_default();
+    AIRDROP_CLAIMED.save(deps.storage, (airdrop_id, word), &(bits | 1u64 << (index % 64)))?;
+
+    let mut token_id:Vec<String> = vec![];
+    let mut token_uri:Vec<String> = vec![];
+    let mut extension:Vec<Extension> = vec![];
+    for _i in 0..amount {
+        let id = next_token_id(deps.storage, &mut config)?;
+        let (uri, ext) = token_metadata(deps.storage, id, airdrop.token_uri.clone(), airdrop.extension.clone())?;
+        token_id.push(id.to_string());
+        token_uri.push(uri);
+        extension.push(ext);
+    }
+
+    let mint_msg = Cw721ExecuteMsg::BatchMint(BatchMintMsg::<Extension> {
+        token_id,
+        owner: vec![info.sender.to_string(); amount as usize],
+        token_uri,
+        extension,
     });
 
     let callback = CosmosMsg::Wasm(WasmMsg::Execute {
@@ -268,26 +1134,101 @@ pub fn execute_mint(
         funds: vec![],
     });
 
-    config.unused_token_id += 1;
     CONFIG.save(deps.storage, &config)?;
 
-    Ok(Response::new().add_message(callback))
+    Ok(Response::new()
+        .add_message(callback)
+        .add_attribute("action", "claim_airdrop")
+        .add_attribute("recipient", info.sender)
+        .add_attribute("airdrop_id", airdrop_id.to_string())
+        .add_attribute("index", index.to_string())
+        .add_attribute("amount", amount.to_string())
+    )
+}
+
+fn next_token_id(
+    storage: &mut dyn Storage,
+    config: &mut Config
+) -> Result<u32, crate::ContractError> {
+    if util::remaining_tokens(config) == 0 {
+        return Err(crate::ContractError::MaxTokensExceed {});
+    }
+
+    let token_id = config.unused_token_id;
+    let minted = util::minted_supply(storage, config)?;
+    MINTED_SUPPLY.save(storage, &(minted + 1))?;
+    config.unused_token_id += 1;
+    Ok(token_id)
 }
 
+// Picks one of the metadata ids not drawn yet, Fisher-Yates over 1..=max_tokens
+fn draw_metadata_id(
+    storage: &mut dyn Storage
+) -> Result<u32, crate::ContractError> {
+    let pool = SHUFFLE_POOL.load(storage)?;
+    if pool == 0 {
+        return Err(crate::ContractError::MaxTokensExceed {});
+    }
+
+    let mut hasher = sha2::Sha256::new();
+    hasher.update(SHUFFLE_SEED.load(storage)?);
+    hasher.update(pool.to_be_bytes());
+    let seed: [u8; 32] = hasher.finalize().into();
+    SHUFFLE_SEED.save(storage, &seed)?;
+
+    let position = (u64::from_be_bytes(seed[..8].try_into().unwrap()) % pool as u64) as u32;
+    let last = pool - 1;
+    let metadata_id = SHUFFLE_SWAP.may_load(storage, position)?.unwrap_or(position + 1);
+    if position != last {
+        let last_id = SHUFFLE_SWAP.may_load(storage, last)?.unwrap_or(last + 1);
+        SHUFFLE_SWAP.save(storage, position, &last_id)?;
+    }
+    SHUFFLE_SWAP.remove(storage, last);
+    SHUFFLE_POOL.save(storage, &last)?;
+    Ok(metadata_id)
+}
+
+// Replaces the requested metadata of a new token while a reveal is configured
+fn token_metadata(
+    storage: &mut dyn Storage,
+    token_id: u32,
+    uri: String,
+    extension: Extension
+) -> Result<(String, Extension), crate::ContractError> {
+    let mut reveal = match REVEAL.may_load(storage)? {
+        Some(reveal) => reveal,
+        None => return Ok((uri, extension))
+    };
+
+    if let Some(base_uri) = reveal.base_uri {
+        return Ok((format!("{}{}", base_uri, token_id), None));
+    }
+
+    UNREVEALED.save(storage, token_id, &true)?;
+    reveal.unrevealed += 1;
+    REVEAL.save(storage, &reveal)?;
+    Ok((reveal.placeholder_uri, None))
+}
+
+fn is_airdrop_claimed(
+    storage: &dyn Storage,
+    airdrop_id: u32,
+    index: u32
+) -> StdResult<bool> {
+    let bits = AIRDROP_CLAIMED.may_load(storage, (airdrop_id, index / 64))?.unwrap_or_default();
+    Ok(bits & 1u64 << (index % 64) != 0)
+}
 
 pub fn execute_batch_mint(
     deps: DepsMut,
-    env: Env,
     info: MessageInfo,
     uri: Vec<String>,
     extension: Vec<Extension>,
     owner: Vec<String>
 ) -> Result<Response, crate::ContractError> {
-    util::check_enabled(deps.storage)?;
+    util::check_not_paused(deps.storage, PauseScope::Mint)?;
+    marble_roles::check_role(deps.storage, &CONFIG, &info.sender, Role::Minter)?;
     let mut config = CONFIG.load(deps.storage)?;
-    if info.sender != config.owner {
-        return Err(crate::ContractError::Unauthorized {});
-    }
 
     if uri.len() != extension.len() {
         return Err(crate::ContractError::CountNotMatch {});
@@ -297,22 +1238,26 @@ pub fn execute_batch_mint(
         return Err(crate::ContractError::Uninitialized {});
     }
 
-    if config.unused_token_id >= config.max_tokens {
+    if util::remaining_tokens(&config) == 0 {
         return Err(crate::ContractError::MaxTokensExceed {});
     }
 
-    let count = uri.len();
     let mut token_id:Vec<String> = vec![];
-    for _i in 0..count {
-        token_id.push(config.unused_token_id.to_string());
-        config.unused_token_id += 1;
+    let mut token_uri:Vec<String> = vec![];
+    let mut token_extension:Vec<Extension> = vec![];
+    for (uri, extension) in uri.into_iter().zip(extension) {
+        let id = next_token_id(deps.storage, &mut config)?;
+        let (uri, extension) = token_metadata(deps.storage, id, uri, extension)?;
+        token_id.push(id.to_string());
+        token_uri.push(uri);
+        token_extension.push(extension);
     }
 
     let mint_msg = Cw721ExecuteMsg::BatchMint(BatchMintMsg::<Extension> {
         token_id,
         owner,
-        token_uri: uri,
-        extension: extension.clone(),
+        token_uri,
+        extension: token_extension,
     });
 
     let callback = CosmosMsg::Wasm(WasmMsg::Execute {
@@ -323,83 +1268,225 @@ pub fn execute_batch_mint(
 
     CONFIG.save(deps.storage, &config)?;
 
-    Ok(Response::new().add_message(callback))
+    Ok(Response::new().add_message(callback))
+}
+
+
+pub fn execute_receive_nft(
+    deps: DepsMut,
+    info: MessageInfo,
+    wrapper: Cw721ReceiveMsg
+) -> Result<Response, crate::ContractError> {
+    util::check_not_paused(deps.storage, PauseScope::List)?;
+    util::check_listable_cw721(deps.storage, &info.sender)?;
+
+    let token_id = wrapper.token_id.clone();
+    let user_addr = deps.api.addr_validate(wrapper.sender.as_str())?;
+
+    let msg: NftReceiveMsg = from_binary(&wrapper.msg)?;
+
+    if SALE.has(deps.storage, (&info.sender, token_id.clone())) {
+        return Err(crate::ContractError::AlreadyOnSale {});
+    }
+
+    match msg {
+        NftReceiveMsg::StartSale {sale_type, duration_type, initial_price, reserve_price, denom} => {
+            start_sale(deps.storage, user_addr, info.sender.clone(), SaleItem {
+                cw721_address: Some(info.sender.clone()),
+                token_id,
+                sale_type,
+                duration_type,
+                initial_price,
+                reserve_price,
+                denom
+            })
+        }
+    }
+}
+
+fn start_sale(
+    storage: &mut dyn Storage,
+    provider: Addr,
+    cw721_address: Addr,
+    item: SaleItem
+) -> Result<Response, crate::ContractError> {
+    util::check_sale_item(storage, &item)?;
+
+    let info = SaleInfo {
+        token_id: item.token_id.clone(),
+        provider,
+        sale_type: item.sale_type,
+        duration_type: item.duration_type,
+        initial_price: item.initial_price,
+        reserve_price: item.reserve_price,
+        requests: vec![],
+        denom: item.denom,
+        can_accept: false,
+        cw721_address: Some(cw721_address.clone()),
+        bid_count: 0,
+        edits: vec![]
+    };
+
+    SALE.save(storage, (&cw721_address, item.token_id.clone()), &info)?;
+    Ok(Response::new()
+        .add_attribute("action", "start_sale")
+        .add_attribute("cw721_address", cw721_address.to_string())
+        .add_attribute("token_id", item.token_id.clone())
+        .add_attribute("initial_price", item.initial_price)
+        .add_attribute("reserve_price", item.reserve_price)
+    )
+}
+
+fn check_batch_size(
+    len: usize
+) -> Result<(), crate::ContractError> {
+    if len == 0 || len > MAX_BATCH_SIZE {
+        return Err(crate::ContractError::InvalidBatchSize {});
+    }
+    Ok(())
+}
+
+pub fn execute_batch_start_sale(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    items: Vec<SaleItem>
+) -> Result<Response, crate::ContractError> {
+    util::check_not_paused(deps.storage, PauseScope::List)?;
+    check_batch_size(items.len())?;
+
+    let mut msgs: Vec<CosmosMsg> = vec![];
+    let mut token_ids: Vec<String> = vec![];
+    for item in items {
+        let cw721_address = util::resolve_cw721_address(deps.storage, item.cw721_address.clone())?;
+        util::check_listable_cw721(deps.storage, &cw721_address)?;
+        // the contract is approved as an operator, so make sure the sender owns what it lists
+        let owner: OwnerOfResponse = deps.querier.query_wasm_smart(
+            cw721_address.clone(),
+            &Cw721QueryMsg::OwnerOf { token_id: item.token_id.clone(), include_expired: None }
+        )?;
+        if owner.owner != info.sender {
+            return Err(crate::ContractError::Unauthorized {});
+        }
+        if SALE.has(deps.storage, (&cw721_address, item.token_id.clone())) {
+            return Err(crate::ContractError::AlreadyOnSale {});
+        }
+
+        token_ids.push(item.token_id.clone());
+        msgs.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: cw721_address.to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw721ExecuteMsg::<Extension>::TransferNft {
+                recipient: env.contract.address.to_string(),
+                token_id: item.token_id.clone()
+            })?,
+        }));
+        start_sale(deps.storage, info.sender.clone(), cw721_address.clone(), item)?;
+    }
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_attribute("action", "batch_start_sale")
+        .add_attribute("token_ids", token_ids.join(","))
+    )
 }
 
-
-pub fn execute_receive_nft(
-    deps: DepsMut,
+pub fn execute_batch_cancel_sale(
+    mut deps: DepsMut,
     info: MessageInfo,
-    wrapper: Cw721ReceiveMsg
+    cw721_address: Addr,
+    token_ids: Vec<String>
 ) -> Result<Response, crate::ContractError> {
-    util::check_enabled(deps.storage)?;
-    let cfg = CONFIG.load(deps.storage)?;
+    check_batch_size(token_ids.len())?;
 
-    if info.sender.clone() != cfg.cw721_address.clone().unwrap() {
-        return Err(crate::ContractError::InvalidCw721Token {})
+    let mut res = Response::new();
+    for token_id in token_ids.iter() {
+        let cancel = execute_cancel_sale(deps.branch(), info.clone(), cw721_address.clone(), token_id.clone())?;
+        res = res.add_submessages(cancel.messages);
     }
 
-    let token_id = wrapper.token_id.clone();
-    let user_addr = deps.api.addr_validate(wrapper.sender.as_str())?;
+    Ok(res
+        .add_attribute("action", "batch_cancel_sale")
+        .add_attribute("token_ids", token_ids.join(","))
+    )
+}
 
-    let msg: NftReceiveMsg = from_binary(&wrapper.msg)?;
+pub fn execute_batch_edit_sale(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    items: Vec<SaleItem>
+) -> Result<Response, crate::ContractError> {
+    check_batch_size(items.len())?;
 
-    if SALE.has(deps.storage, token_id.clone()) {
-        return Err(crate::ContractError::AlreadyOnSale {});
+    let mut token_ids: Vec<String> = vec![];
+    for item in items {
+        token_ids.push(item.token_id.clone());
+        execute_edit_sale(deps.branch(), env.clone(), info.clone(), item)?;
     }
 
-    match msg {
-        NftReceiveMsg::StartSale {sale_type, duration_type, initial_price, reserve_price, denom} => {
-            if sale_type == SaleType::Fixed && duration_type != DurationType::Fixed {
-                return Err(crate::ContractError::InvalidSaleType {});
-            }
-
-            match duration_type.clone() {
-                DurationType::Time(start, end) => {
-                    if start >= end {
-                        return Err(crate::ContractError::DurationIncorrect {});
-                    }
-                },
-                DurationType::Fixed => {},
-                DurationType::Bid(_count) => {}
-            }
+    Ok(Response::new()
+        .add_attribute("action", "batch_edit_sale")
+        .add_attribute("token_ids", token_ids.join(","))
+    )
+}
 
-            let info = SaleInfo {
-                token_id: token_id.parse().unwrap(),
-                provider: user_addr.clone(),
-                sale_type,
-                duration_type,
-                initial_price,
-                reserve_price,
-                requests: vec![],
-                denom,
-                can_accept: false
-            };
-
-            SALE.save(deps.storage, token_id.clone(), &info)?;
-            Ok(Response::new()
-                .add_attribute("action", "start_sale")
-                .add_attribute("token_id", token_id.clone())
-                .add_attribute("initial_price", initial_price)
-                .add_attribute("reserve_price", reserve_price)
-            )
+pub fn execute_sweep_buy(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    cw721_address: Addr,
+    token_ids: Vec<String>
+) -> Result<Response, crate::ContractError> {
+    check_batch_size(token_ids.len())?;
+
+    let mut required: BTreeMap<String, Uint128> = BTreeMap::new();
+    let mut res = Response::new();
+    for token_id in token_ids.iter() {
+        let sale_info = SALE.may_load(deps.storage, (&cw721_address, token_id.clone()))?.ok_or(crate::ContractError::NotOnSale {})?;
+        if sale_info.sale_type != SaleType::Fixed {
+            return Err(crate::ContractError::InvalidSaleType {});
         }
+        let denom = match sale_info.denom.clone() {
+            Denom::Native(denom) => denom,
+            Denom::Cw20(_) => return Err(crate::ContractError::OnlyNativeSell {})
+        };
+        *required.entry(denom).or_default() += sale_info.initial_price;
+
+        let buy = handle_propose(deps.branch(), env.clone(), cw721_address.clone(), token_id.clone(), info.sender.clone(), sale_info.initial_price)?;
+        res = res.add_submessages(buy.messages);
+    }
+
+    let mut funds: BTreeMap<String, Uint128> = BTreeMap::new();
+    for coin in info.funds.iter() {
+        *funds.entry(coin.denom.clone()).or_default() += coin.amount;
     }
+    if funds != required {
+        return Err(crate::ContractError::WrongPaymentAmount {});
+    }
+
+    Ok(res
+        .add_attribute("action", "sweep_buy")
+        .add_attribute("address", info.sender)
+        .add_attribute("token_ids", token_ids.join(","))
+    )
 }
 
 pub fn execute_accept_sale(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
-    token_id: u32
+    cw721_address: Addr,
+    token_id: String
 ) -> Result<Response, crate::ContractError> {
 
-    util::check_enabled(deps.storage)?;
+    util::check_not_paused(deps.storage, PauseScope::Settle)?;
 
-    if !SALE.has(deps.storage, token_id.to_string()) {
+    if !SALE.has(deps.storage, (&cw721_address, token_id.clone())) {
         return Err(crate::ContractError::NotOnSale {});
     }
 
-    let sale_info = SALE.load(deps.storage, token_id.to_string())?;
+    let sale_info = SALE.load(deps.storage, (&cw721_address, token_id.clone()))?;
 
     if sale_info.provider != info.sender {
         return Err(crate::ContractError::Unauthorized {  });
@@ -413,7 +1500,8 @@ pub fn execute_accept_sale(
     let len = sale_info.requests.len();
     let sell_request = list.get(len - 1).unwrap();
     //Add NFT send msg
-    let mut msgs = sell_nft_messages(deps.storage, deps.api, sell_request.address.clone(), sell_request.price.clone(), sale_info.clone())?;
+    record_trade(deps.storage, &env, &sale_info, sell_request.address.clone(), sell_request.price)?;
+    let mut msgs = sell_nft_messages(deps.storage, deps.api, deps.querier, sell_request.address.clone(), sell_request.price.clone(), sale_info.clone())?;
 
     //Add return fund msg
     for i in 0..len - 1 {
@@ -421,12 +1509,16 @@ pub fn execute_accept_sale(
         msgs.push(util::transfer_token_message(sale_info.denom.clone(), request.price, request.address.clone())?);
     }
 
-    SALE.remove(deps.storage, token_id.to_string());
+    for request in list.iter() {
+        util::remove_escrow(deps.storage, (&cw721_address, &token_id), &sale_info.denom, request.price)?;
+    }
+    SALE.remove(deps.storage, (&cw721_address, token_id.clone()));
 
     Ok(Response::new()
         .add_messages(msgs)
         .add_attribute("action", "accept_sale")
-        .add_attribute("token_id", token_id.to_string())
+        .add_attribute("cw721_address", cw721_address.to_string())
+        .add_attribute("token_id", token_id.clone())
         .add_attribute("address", sell_request.address.clone().to_string())
         .add_attribute("price", sell_request.price)
     )
@@ -435,50 +1527,109 @@ pub fn execute_accept_sale(
 pub fn execute_cancel_sale(
     deps: DepsMut,
     info: MessageInfo,
-    token_id: u32
+    cw721_address: Addr,
+    token_id: String
 ) -> Result<Response, crate::ContractError> {
 
-    util::check_enabled(deps.storage)?;
-
-    if !SALE.has(deps.storage, token_id.to_string()) {
+    if !SALE.has(deps.storage, (&cw721_address, token_id.clone())) {
         return Err(crate::ContractError::NotOnSale {});
     }
 
-    let sale_info = SALE.load(deps.storage, token_id.to_string())?;
+    let sale_info = SALE.load(deps.storage, (&cw721_address, token_id.clone()))?;
 
-    if sale_info.provider != info.sender {
+    // moderators can delist any sale, the NFT still goes back to its provider
+    if sale_info.provider != info.sender && !marble_roles::has_role(deps.storage, &CONFIG, &info.sender, &Role::SaleModerator)? {
         return Err(crate::ContractError::Unauthorized {  });
     }
 
-    if sale_info.can_accept {
+    if sale_info.can_accept && !util::is_disabled(deps.storage)? {
         return Err(crate::ContractError::CannotCancelSale {  });
     }
 
-    let cfg = CONFIG.load(deps.storage)?;
+    let msgs = close_sale_messages(deps.storage, sale_info)?;
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_attribute("action", "cancel_sale")
+        .add_attribute("cw721_address", cw721_address.to_string())
+        .add_attribute("token_id", token_id.clone())
+    )
+}
+
+// Hands the NFT back to the provider, refunds every bid and removes the sale
+fn close_sale_messages(
+    storage: &mut dyn Storage,
+    sale_info: SaleInfo
+) -> Result<Vec<CosmosMsg>, crate::ContractError> {
+    let cw721_address = util::sale_cw721_address(storage, &sale_info)?;
 
     let mut msgs: Vec<CosmosMsg> = vec![];
     msgs.push(CosmosMsg::Wasm(WasmMsg::Execute {
-        contract_addr: cfg.cw721_address.clone().unwrap().to_string(),
+        contract_addr: cw721_address.to_string(),
         funds: vec![],
         msg: to_binary(&Cw721ExecuteMsg::<Extension>::TransferNft {
-            recipient: info.sender.clone().into(),
-            token_id: sale_info.token_id.to_string()
+            recipient: sale_info.provider.clone().into(),
+            token_id: sale_info.token_id.clone()
         })?,
     }));
 
-    let list = sale_info.requests.clone();
     //Add return fund msg
-    for i in 0..list.len() {
-        let request = list.get(i).unwrap();
+    for request in sale_info.requests.iter() {
         msgs.push(util::transfer_token_message(sale_info.denom.clone(), request.price, request.address.clone())?);
+        util::remove_escrow(storage, (&cw721_address, &sale_info.token_id), &sale_info.denom, request.price)?;
+    }
+
+    SALE.remove(storage, (&cw721_address, sale_info.token_id.clone()));
+    Ok(msgs)
+}
+
+pub fn execute_unwind_all_sales(
+    deps: DepsMut,
+    info: MessageInfo,
+    limit: Option<u32>
+) -> Result<Response, crate::ContractError> {
+    marble_roles::check_role(deps.storage, &CONFIG, &info.sender, Role::SaleModerator)?;
+    if !util::is_disabled(deps.storage)? {
+        return Err(crate::ContractError::NotDisabled {});
+    }
+
+    let limit = limit.unwrap_or(MAX_BATCH_SIZE as u32).min(MAX_BATCH_SIZE as u32) as usize;
+    let sales: StdResult<Vec<_>> = SALE
+        .range(deps.storage, None, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_key, sale_info)| sale_info))
+        .collect();
+
+    let mut msgs: Vec<CosmosMsg> = vec![];
+    let mut token_ids: Vec<String> = vec![];
+    for sale_info in sales? {
+        token_ids.push(sale_info.token_id.clone());
+        msgs.extend(close_sale_messages(deps.storage, sale_info)?);
     }
 
-    SALE.remove(deps.storage, token_id.to_string());
+    let remaining = SALE.keys(deps.storage, None, None, Order::Ascending).next().is_some();
 
     Ok(Response::new()
         .add_messages(msgs)
-        .add_attribute("action", "cancel_sale")
-        .add_attribute("token_id", token_id.to_string())
+        .add_attribute("action", "unwind_all_sales")
+        .add_attribute("token_ids", token_ids.join(","))
+        .add_attribute("remaining", remaining.to_string())
+    )
+}
+
+pub fn execute_rebuild_escrow(
+    deps: DepsMut,
+    info: MessageInfo,
+    limit: Option<u32>
+) -> Result<Response, crate::ContractError> {
+    marble_roles::check_role(deps.storage, &CONFIG, &info.sender, Role::SaleModerator)?;
+
+    let limit = limit.unwrap_or(MAX_BATCH_SIZE as u32).clamp(1, MAX_BATCH_SIZE as u32) as usize;
+    let complete = util::rebuild_escrow(deps.storage, limit)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "rebuild_escrow")
+        .add_attribute("complete", complete.to_string())
     )
 }
 
@@ -487,19 +1638,82 @@ pub fn execute_propose(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    token_id: u32,
-    denom: String
+    cw721_address: Addr,
+    token_id: String,
+    denom: String,
+    min_output: Option<Uint128>
 ) -> Result<Response, crate::ContractError> {
 
-    let sale_info = SALE.load(deps.storage, token_id.to_string())?;
+    let sale_info = SALE.load(deps.storage, (&cw721_address, token_id.clone()))?;
+    let amount = util::get_amount_of_denom(Balance::from(info.funds), Denom::Native(denom.clone()))?;
+
     if sale_info.denom != Denom::Native(denom.clone()) {
-        return Err(crate::ContractError::InvalidNativeToken {})
+        return handle_swap_propose(deps, env, cw721_address, token_id, info.sender.clone(), Denom::Native(denom), amount, min_output);
     }
 
-    let amount = util::get_amount_of_denom(Balance::from(info.funds), Denom::Native(denom.clone()))?;
+    handle_propose(deps, env, cw721_address, token_id, info.sender.clone(), amount)
+
+}
+
+pub fn execute_sweep(
+    mut deps: DepsMut,
+    env: Env,
+    buyer: Addr,
+    payment: Balance,
+    cw721_address: Addr,
+    token_ids: Vec<String>,
+    max_total: Uint128
+) -> Result<Response, crate::ContractError> {
+    util::check_not_paused(deps.storage, PauseScope::Bid)?;
+    check_batch_size(token_ids.len())?;
 
-    handle_propose(deps, env, token_id, info.sender.clone(), amount)
+    let (denom, amount) = match payment {
+        Balance::Native(native) if native.0.len() == 1 => (Denom::Native(native.0[0].denom.clone()), native.0[0].amount),
+        Balance::Cw20(coin) => (Denom::Cw20(coin.address), coin.amount),
+        _ => return Err(crate::ContractError::IncorrectFunds {})
+    };
+    let budget = amount.min(max_total);
+
+    let mut spent = Uint128::zero();
+    let mut over_budget = false;
+    let mut list: Vec<SweepItem> = vec![];
+    let mut msgs: Vec<SubMsg> = vec![];
+    for token_id in token_ids {
+        let result = match SALE.may_load(deps.storage, (&cw721_address, token_id.clone()))? {
+            None => SweepResult::NotOnSale,
+            Some(sale_info) if sale_info.sale_type != SaleType::Fixed => SweepResult::NotFixedSale,
+            Some(sale_info) if sale_info.denom != denom => SweepResult::DenomMismatch,
+            Some(sale_info) => {
+                // listings are bought in the given order, so the sweep ends at the first one over budget
+                over_budget = over_budget || spent + sale_info.initial_price > budget;
+                if over_budget {
+                    SweepResult::OverBudget
+                } else {
+                    let res = handle_propose(deps.branch(), env.clone(), cw721_address.clone(), token_id.clone(), buyer.clone(), sale_info.initial_price)?;
+                    msgs.extend(res.messages);
+                    spent += sale_info.initial_price;
+                    SweepResult::Bought { price: sale_info.initial_price }
+                }
+            }
+        };
+        list.push(SweepItem { token_id, result });
+    }
+
+    let refund = amount - spent;
+    if !refund.is_zero() {
+        msgs.push(SubMsg::new(util::transfer_token_message(denom, refund, buyer.clone())?));
+    }
 
+    let bought = list.iter().filter(|item| matches!(item.result, SweepResult::Bought { .. })).count();
+    Ok(Response::new()
+        .add_submessages(msgs)
+        .set_data(to_binary(&SweepResponse { spent, refund, list })?)
+        .add_attribute("action", "sweep")
+        .add_attribute("address", buyer)
+        .add_attribute("bought", bought.to_string())
+        .add_attribute("spent", spent)
+        .add_attribute("refund", refund)
+    )
 }
 
 pub fn execute_receive(
@@ -515,30 +1729,93 @@ pub fn execute_receive(
     let cw20_amount = wrapper.amount;
 
     match msg {
-        ReceiveMsg::Propose { token_id } => {
-
-            let sale_info = SALE.load(deps.storage, token_id.to_string())?;
+        ReceiveMsg::Propose { cw721_address, token_id, min_output } => {
+            let cw721_address = util::resolve_cw721_address(deps.storage, cw721_address)?;
+            let sale_info = SALE.load(deps.storage, (&cw721_address, token_id.clone()))?;
             if sale_info.denom != Denom::Cw20(info.sender.clone()) {
-                return Err(crate::ContractError::InvalidCw20Token {})
+                return handle_swap_propose(deps, env, cw721_address, token_id, user_addr.clone(), Denom::Cw20(info.sender.clone()), cw20_amount, min_output);
             }
-            handle_propose(deps, env, token_id, user_addr.clone(), cw20_amount)
+            handle_propose(deps, env, cw721_address, token_id, user_addr.clone(), cw20_amount)
+        },
+        ReceiveMsg::Mint { uri, extension, proof } => {
+            let payment = Balance::Cw20(Cw20CoinVerified {
+                address: info.sender.clone(),
+                amount: cw20_amount
+            });
+            execute_mint(deps, env, user_addr.clone(), payment, uri, extension, proof)
+        },
+        ReceiveMsg::Sweep { cw721_address, token_ids, max_total } => {
+            let cw721_address = util::resolve_cw721_address(deps.storage, cw721_address)?;
+            let payment = Balance::Cw20(Cw20CoinVerified {
+                address: info.sender.clone(),
+                amount: cw20_amount
+            });
+            execute_sweep(deps, env, user_addr.clone(), payment, cw721_address, token_ids, max_total)
         }
     }
 }
 
+// Converts the buyer's payment into the sale denom through the registered pools before proposing.
+// Fixed sales only take the listing price, and the rest of the swap output is refunded to the buyer.
+#[allow(clippy::too_many_arguments)]
+pub fn handle_swap_propose(
+    deps: DepsMut,
+    env: Env,
+    cw721_address: Addr,
+    token_id: String,
+    address: Addr,
+    denom: Denom,
+    amount: Uint128,
+    min_output: Option<Uint128>
+) -> Result<Response, crate::ContractError> {
+
+    let sale_info = SALE.load(deps.storage, (&cw721_address, token_id.clone()))?;
+
+    let (swap_amount, swap_msgs) = util::get_swap_route_messages(deps.querier, deps.storage, denom.clone(), amount, sale_info.denom.clone())?;
+
+    let min_output = min_output.unwrap_or_default();
+    if swap_amount < min_output {
+        return Err(crate::ContractError::SwapOutputTooLow { output: swap_amount, min_output });
+    }
+
+    let mut price = swap_amount;
+    if sale_info.sale_type == SaleType::Fixed && swap_amount > sale_info.initial_price {
+        price = sale_info.initial_price;
+    }
+
+    let res = handle_propose(deps, env, cw721_address, token_id, address.clone(), price)?;
+
+    let mut msgs: Vec<CosmosMsg> = vec![];
+    if swap_amount > price {
+        msgs.push(util::transfer_token_message(sale_info.denom.clone(), swap_amount - price, address.clone())?);
+    }
+
+    Ok(Response::new()
+        .add_messages(swap_msgs)
+        .add_submessages(res.messages)
+        .add_messages(msgs)
+        .add_attributes(res.attributes)
+        .add_attribute("swap_denom", util::denom_key(&denom))
+        .add_attribute("swap_amount", amount)
+        .add_attribute("refund", swap_amount - price)
+    )
+}
+
 pub fn handle_propose(
     deps: DepsMut,
     env: Env,
-    token_id: u32,
+    cw721_address: Addr,
+    token_id: String,
     address: Addr,
     price: Uint128
 ) -> Result<Response, crate::ContractError> {
 
-    util::check_enabled(deps.storage)?;
-    if !SALE.has(deps.storage, token_id.to_string()) {
+    util::check_not_paused(deps.storage, PauseScope::Bid)?;
+    let key = (&cw721_address, token_id.clone());
+    if !SALE.has(deps.storage, key.clone()) {
         return Err(crate::ContractError::NotOnSale {});
     }
-    let mut sale_info = SALE.load(deps.storage, token_id.to_string())?;
+    let mut sale_info = SALE.load(deps.storage, key.clone())?;
 
     match sale_info.duration_type.clone() {
         DurationType::Fixed => {
@@ -579,7 +1856,10 @@ pub fn handle_propose(
     //     let len = list.len();
     //     lastitem = list[len - 1].clone();
     // }
+    number_bids(&mut sale_info);
+    sale_info.bid_count += 1;
     list.push(Request {
+        id: sale_info.bid_count,
         address: address.clone(),
         price
     });
@@ -590,24 +1870,27 @@ pub fn handle_propose(
         sale_info.can_accept = true;
     }
 
-    SALE.save(deps.storage, token_id.to_string(), &sale_info)?;
+    SALE.save(deps.storage, key.clone(), &sale_info)?;
 
     //Handle Fixed
     if sale_info.sale_type == SaleType::Fixed {
         //send NFT messages
-        let msgs = sell_nft_messages(deps.storage, deps.api, address.clone(), price, sale_info)?;
+        record_trade(deps.storage, &env, &sale_info, address.clone(), price)?;
+        let msgs = sell_nft_messages(deps.storage, deps.api, deps.querier, address.clone(), price, sale_info)?;
         //Remove Entry
-        SALE.remove(deps.storage, token_id.to_string());
+        SALE.remove(deps.storage, key);
 
         return Ok(Response::new()
             .add_messages(msgs)
             .add_attribute("action", "fixed_sell")
             .add_attribute("address", address.clone())
-            .add_attribute("token_id", token_id.to_string())
+            .add_attribute("cw721_address", cw721_address.to_string())
+            .add_attribute("token_id", token_id.clone())
             .add_attribute("price", price)
         );
 
     } else {
+        util::add_escrow(deps.storage, (&cw721_address, &token_id), &sale_info.denom, price)?;
         let mut msgs:Vec<CosmosMsg> = vec![];
         // if list.len() > 1 {
         //     msgs.push(util::transfer_token_message(sale_info.denom.clone(), lastitem.price, lastitem.address.clone())?);
@@ -617,7 +1900,8 @@ pub fn handle_propose(
             .add_messages(msgs)
             .add_attribute("action", "propose")
             .add_attribute("address", address.clone())
-            .add_attribute("token_id", token_id.to_string())
+            .add_attribute("cw721_address", cw721_address.to_string())
+            .add_attribute("token_id", token_id.clone())
             .add_attribute("price", price)
         )
     }
@@ -625,41 +1909,115 @@ pub fn handle_propose(
 
 const MULTIPLY:u32 = 1000000u32;
 
+fn record_trade(
+    storage: &mut dyn Storage,
+    env: &Env,
+    sale_info: &SaleInfo,
+    buyer: Addr,
+    price: Uint128
+) -> Result<(), crate::ContractError> {
+    let cw721_address = util::sale_cw721_address(storage, sale_info)?;
+    let id = TRADE_COUNT.may_load(storage)?.unwrap_or_default() + 1;
+    TRADE_COUNT.save(storage, &id)?;
+
+    TRADES.save(storage, id, &Trade {
+        id,
+        cw721_address: cw721_address.clone(),
+        token_id: sale_info.token_id.clone(),
+        seller: sale_info.provider.clone(),
+        buyer: buyer.clone(),
+        price,
+        denom: sale_info.denom.clone(),
+        time: env.block.time.seconds(),
+        sale_type: sale_info.sale_type.clone()
+    })?;
+    TOKEN_TRADES.save(storage, (&cw721_address, sale_info.token_id.clone(), id), &true)?;
+    CW721_TRADES.save(storage, (&cw721_address, id), &true)?;
+    ACCOUNT_TRADES.save(storage, (&sale_info.provider, id), &true)?;
+    ACCOUNT_TRADES.save(storage, (&buyer, id), &true)?;
+    Ok(())
+}
+
+// Royalty payouts of a sale price. Tokens of the collection's own contract pay the configured
+// royalties and whitelisted contracts pay what their royalty source says
+fn royalty_payouts(
+    storage: &dyn Storage,
+    api: &dyn Api,
+    querier: QuerierWrapper,
+    sale_info: &SaleInfo,
+    amount: Uint128
+) -> Result<Vec<(Addr, Uint128)>, crate::ContractError> {
+    let cfg = CONFIG.load(storage)?;
+    let cw721_address = util::sale_cw721_address(storage, sale_info)?;
+
+    let royalties = if cfg.cw721_address.as_ref() == Some(&cw721_address) {
+        cfg.royalties
+    } else {
+        match CW721_WHITELIST.may_load(storage, &cw721_address)? {
+            Some(RoyaltySource::Registry { royalties }) => royalties,
+            Some(RoyaltySource::Cw2981) => {
+                let max_amount = amount * Uint128::from(cfg.maximum_royalty_fee) / Uint128::from(MULTIPLY);
+                return Ok(cw2981_royalty(api, querier, cw721_address, sale_info.token_id.clone(), amount)
+                    .map(|(address, royalty)| vec![(address, royalty.min(max_amount))])
+                    .unwrap_or_default());
+            },
+            Some(RoyaltySource::None) | None => vec![]
+        }
+    };
+
+    Ok(royalties.into_iter()
+        .map(|item| (item.address, amount * Uint128::from(item.rate) / Uint128::from(MULTIPLY)))
+        .collect())
+}
+
+// A contract that can't answer, or names an invalid receiver, pays no royalty rather than locking the sale
+fn cw2981_royalty(
+    api: &dyn Api,
+    querier: QuerierWrapper,
+    cw721_address: Addr,
+    token_id: String,
+    sale_price: Uint128
+) -> Option<(Addr, Uint128)> {
+    let query = Cw2981QueryMsg::Extension {
+        msg: Cw2981Query::RoyaltyInfo { token_id: token_id.clone(), sale_price }
+    };
+    let info: RoyaltiesInfoResponse = querier.query_wasm_smart(cw721_address, &query).ok()?;
+    let address = api.addr_validate(&info.address).ok()?;
+    Some((address, info.royalty_amount))
+}
+
 pub fn sell_nft_messages (
     storage: &mut dyn Storage,
     api: &dyn Api,
+    querier: QuerierWrapper,
     recipient: Addr,
     amount: Uint128,
     sale_info: SaleInfo
 ) -> Result<Vec<CosmosMsg>, crate::ContractError> {
-    let cfg = CONFIG.load(storage)?;
-    let mut list:Vec<Request> = vec![];
+    let mut list = royalty_payouts(storage, api, querier, &sale_info, amount)?;
 
     let mut provider_amount = amount;
-
-    for item in cfg.royalties {
-        let amount = amount * Uint128::from(item.rate) / Uint128::from(MULTIPLY);
-        provider_amount -= amount;
-        list.push(Request { address: item.address.clone(), price: amount });
+    for (_address, royalty) in list.iter() {
+        provider_amount -= *royalty;
     }
 
-    list.push(Request { address: sale_info.provider.clone(), price: provider_amount });
+    list.push((sale_info.provider.clone(), provider_amount));
 
     let mut msgs: Vec<CosmosMsg> = vec![];
     msgs.push(CosmosMsg::Wasm(WasmMsg::Execute {
-        contract_addr: cfg.cw721_address.clone().unwrap().to_string(),
+        contract_addr: util::sale_cw721_address(storage, &sale_info)?.to_string(),
         funds: vec![],
         msg: to_binary(&Cw721ExecuteMsg::<Extension>::TransferNft {
             recipient: recipient.clone().into(),
-            token_id: sale_info.token_id.to_string()
+            token_id: sale_info.token_id.clone()
         })?,
     }));
 
-    for item in list {
-        if item.price == Uint128::zero() {
+    for (address, price) in list {
+        if price == Uint128::zero() {
             continue;
         }
-        msgs.push(util::transfer_token_message(sale_info.denom.clone(), item.price, item.address.clone())?);
+        msgs.push(util::transfer_token_message(sale_info.denom.clone(), price, address)?);
     }
 
     Ok(msgs)
@@ -668,16 +2026,15 @@ pub fn sell_nft_messages (
 
 pub fn execute_edit_sale(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
-    token_id: u32,
-    sale_type: SaleType,
-    duration_type: DurationType,
-    initial_price: Uint128,
-    reserve_price: Uint128,
-    denom: Denom
+    item: SaleItem
 ) -> Result<Response, crate::ContractError> {
+    util::check_not_paused(deps.storage, PauseScope::List)?;
 
-    let mut sale_info = SALE.load(deps.storage, token_id.to_string())?;
+    let token_id = item.token_id.clone();
+    let cw721_address = util::resolve_cw721_address(deps.storage, item.cw721_address.clone())?;
+    let mut sale_info = SALE.load(deps.storage, (&cw721_address, token_id.clone()))?;
     if sale_info.provider != info.sender.clone() {
         return Err(crate::ContractError::Unauthorized {  });
     }
@@ -686,49 +2043,220 @@ pub fn execute_edit_sale(
         return Err(crate::ContractError::AlreadyOnSale {  });
     }
 
-    sale_info.sale_type = sale_type;
-    sale_info.duration_type = duration_type;
-    sale_info.initial_price = initial_price;
-    sale_info.reserve_price = reserve_price;
+    util::check_sale_item(deps.storage, &item)?;
 
-    SALE.save(deps.storage, token_id.to_string(), &sale_info)?;
+    sale_info.edits.push(SaleEdit {
+        time: env.block.time.seconds(),
+        sale_type: sale_info.sale_type,
+        duration_type: sale_info.duration_type,
+        initial_price: sale_info.initial_price,
+        reserve_price: sale_info.reserve_price,
+        denom: sale_info.denom
+    });
+    // every GetSale returns the history, keep only the latest edits
+    if sale_info.edits.len() > MAX_SALE_EDITS {
+        sale_info.edits.drain(..sale_info.edits.len() - MAX_SALE_EDITS);
+    }
+    sale_info.sale_type = item.sale_type;
+    sale_info.duration_type = item.duration_type;
+    sale_info.initial_price = item.initial_price;
+    sale_info.reserve_price = item.reserve_price;
+    sale_info.denom = item.denom;
+
+    SALE.save(deps.storage, (&cw721_address, token_id.clone()), &sale_info)?;
     Ok(Response::new()
         .add_attribute("action", "edit_sale")
-        .add_attribute("token_id", token_id.to_string()))
+        .add_attribute("cw721_address", cw721_address.to_string())
+        .add_attribute("token_id", token_id.clone())
+        .add_attribute("initial_price", item.initial_price)
+        .add_attribute("reserve_price", item.reserve_price))
 }
 
 
+// Bids placed before ids existed all carry id 0, number them in placement order
+fn number_bids(
+    sale_info: &mut SaleInfo
+) {
+    if sale_info.bid_count > 0 || sale_info.requests.is_empty() {
+        return;
+    }
+    for (i, request) in sale_info.requests.iter_mut().enumerate() {
+        request.id = i as u32 + 1;
+    }
+    sale_info.bid_count = sale_info.requests.len() as u32;
+}
+
 pub fn execute_cancel_propose(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
-    token_id: u32,
+    cw721_address: Addr,
+    token_id: String,
+    bid_id: u32
 ) -> Result<Response, crate::ContractError> {
 
-    let mut sale_info = SALE.load(deps.storage, token_id.to_string())?;
-    let list = sale_info.requests.clone();
-    let mut new_list: Vec<Request> = vec![];
-    let mut cancel_price = Uint128::zero();
+    let mut sale_info = SALE.may_load(deps.storage, (&cw721_address, token_id.clone()))?
+        .ok_or(crate::ContractError::NotOnSale {})?;
+    number_bids(&mut sale_info);
 
-    for i in 0.. list.len() {
-        if list[i].address == info.sender.clone() {
-            cancel_price = list[i].price;
-            continue;
-        }
-        new_list.push(list[i].clone());
+    let index = sale_info.requests.iter()
+        .position(|request| request.id == bid_id)
+        .ok_or(crate::ContractError::BidNotFound {})?;
+    let request = sale_info.requests[index].clone();
+    if request.address != info.sender {
+        return Err(crate::ContractError::Unauthorized {});
     }
 
-    sale_info.requests = new_list;
+    // the seller counts on the leading bid, so it can't be pulled while the auction is live
+    // or, once it is over, while the seller can still accept it
+    if index == sale_info.requests.len() - 1 {
+        let live = match sale_info.duration_type {
+            DurationType::Time(_start, end) => env.block.time.seconds() <= end,
+            _ => false
+        };
+        let locked = live || sale_info.can_accept;
+        if locked && !util::is_disabled(deps.storage)? {
+            return Err(crate::ContractError::LeadingBidLocked {});
+        }
+    }
 
-    SALE.save(deps.storage, token_id.to_string(), &sale_info)?;
+    sale_info.requests.remove(index);
+    util::remove_escrow(deps.storage, (&cw721_address, &token_id), &sale_info.denom, request.price)?;
+    sale_info.can_accept = sale_info.sale_type == SaleType::Auction && sale_info.requests.last()
+        .is_some_and(|leading| leading.price >= sale_info.reserve_price);
 
-    let mut msgs: Vec<CosmosMsg> = vec![];
-    msgs.push(util::transfer_token_message(sale_info.denom.clone(), cancel_price, info.sender.clone())?);
+    SALE.save(deps.storage, (&cw721_address, token_id.clone()), &sale_info)?;
 
     Ok(Response::new()
+        .add_message(util::transfer_token_message(sale_info.denom.clone(), request.price, info.sender.clone())?)
         .add_attribute("action", "cancel_propose")
-        .add_attribute("token_id", token_id.to_string())
+        .add_attribute("cw721_address", cw721_address.to_string())
+        .add_attribute("token_id", token_id.clone())
+        .add_attribute("bid_id", bid_id.to_string())
         .add_attribute("address", info.sender.clone().to_string())
-        .add_messages(msgs)
+        .add_attribute("price", request.price)
+    )
+}
+
+
+pub fn execute_add_supported_denom(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: Denom,
+    pool_address: Option<Addr>,
+    decimals: u8,
+    symbol: String
+) -> Result<Response, crate::ContractError> {
+    marble_roles::check_role(deps.storage, &CONFIG, &info.sender, Role::SaleModerator)?;
+
+    if SUPPORTED_DENOMS.has(deps.storage, util::denom_key(&denom)) {
+        return Err(crate::ContractError::DenomAlreadySupported {});
+    }
+
+    save_supported_denom(deps, SupportedDenom {
+        denom,
+        pool_address,
+        decimals,
+        symbol,
+        enabled: true
+    }, "add_supported_denom")
+}
+
+pub fn execute_update_supported_denom(
+    deps: DepsMut,
+    info: MessageInfo,
+    supported: SupportedDenom
+) -> Result<Response, crate::ContractError> {
+    marble_roles::check_role(deps.storage, &CONFIG, &info.sender, Role::SaleModerator)?;
+
+    if !SUPPORTED_DENOMS.has(deps.storage, util::denom_key(&supported.denom)) {
+        return Err(crate::ContractError::UnsupportedDenom {});
+    }
+
+    save_supported_denom(deps, supported, "update_supported_denom")
+}
+
+fn save_supported_denom(
+    deps: DepsMut,
+    supported: SupportedDenom,
+    action: &str
+) -> Result<Response, crate::ContractError> {
+    if let Some(pool_address) = supported.pool_address.clone() {
+        util::check_token_and_pool(deps.querier, supported.denom.clone(), pool_address)?;
+    }
+
+    let key = util::denom_key(&supported.denom);
+    SUPPORTED_DENOMS.save(deps.storage, key.clone(), &supported)?;
+
+    Ok(Response::new()
+        .add_attribute("action", action)
+        .add_attribute("denom", key)
+        .add_attribute("enabled", supported.enabled.to_string())
+    )
+}
+
+pub fn execute_remove_supported_denom(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: Denom
+) -> Result<Response, crate::ContractError> {
+    marble_roles::check_role(deps.storage, &CONFIG, &info.sender, Role::SaleModerator)?;
+
+    let key = util::denom_key(&denom);
+    if !SUPPORTED_DENOMS.has(deps.storage, key.clone()) {
+        return Err(crate::ContractError::UnsupportedDenom {});
+    }
+    SUPPORTED_DENOMS.remove(deps.storage, key.clone());
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_supported_denom")
+        .add_attribute("denom", key)
+    )
+}
+
+pub fn execute_whitelist_cw721(
+    deps: DepsMut,
+    info: MessageInfo,
+    cw721_address: Addr,
+    royalty: RoyaltySource
+) -> Result<Response, crate::ContractError> {
+    marble_roles::check_role(deps.storage, &CONFIG, &info.sender, Role::SaleModerator)?;
+    let config = CONFIG.load(deps.storage)?;
+    // the collection's own contract always lists with the configured royalties
+    if config.cw721_address.as_ref() == Some(&cw721_address) {
+        return Err(crate::ContractError::InvalidCw721Token {});
+    }
+    if let RoyaltySource::Registry { royalties } = &royalty {
+        let sum: u32 = royalties.iter().map(|item| item.rate).sum();
+        if sum > config.maximum_royalty_fee {
+            return Err(crate::ContractError::ExceedsMaximumRoyaltyFee {});
+        }
+    }
+    CW721_WHITELIST.save(deps.storage, &cw721_address, &royalty)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "whitelist_cw721")
+        .add_attribute("cw721_address", cw721_address.to_string())
+    )
+}
+
+pub fn execute_remove_cw721(
+    deps: DepsMut,
+    info: MessageInfo,
+    cw721_address: Addr
+) -> Result<Response, crate::ContractError> {
+    marble_roles::check_role(deps.storage, &CONFIG, &info.sender, Role::SaleModerator)?;
+    if !CW721_WHITELIST.has(deps.storage, &cw721_address) {
+        return Err(crate::ContractError::InvalidCw721Token {});
+    }
+    if SALE.prefix(&cw721_address).keys(deps.storage, None, None, Order::Ascending).next().is_some() {
+        return Err(crate::ContractError::SalesOpen {});
+    }
+    CW721_WHITELIST.remove(deps.storage, &cw721_address);
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_cw721")
+        .add_attribute("cw721_address", cw721_address.to_string())
     )
 }
 
@@ -738,9 +2266,14 @@ pub fn execute_change_contract(
     info: MessageInfo,
     cw721_address: Addr
 ) -> Result<Response, crate::ContractError> {
+    // owner only, no role covers handing over the cw721 contract
+    util::check_owner(deps.storage, info.sender.clone())?;
     let mut config = CONFIG.load(deps.storage)?;
-    if info.sender != config.owner {
-        return Err(crate::ContractError::Unauthorized {});
+    // listed tokens have to be settled or cancelled on the contract that holds them first
+    if let Some(current) = config.cw721_address.clone() {
+        if SALE.prefix(&current).keys(deps.storage, None, None, Order::Ascending).next().is_some() {
+            return Err(crate::ContractError::SalesOpen {});
+        }
     }
     config.cw721_address = Some(cw721_address.clone());
     CONFIG.save(deps.storage, &config)?;
@@ -756,10 +2289,9 @@ pub fn execute_change_cw721_owner(
     info: MessageInfo,
     owner: Addr
 ) -> Result<Response, crate::ContractError> {
+    // owner only, no role covers handing over the cw721 contract
+    util::check_owner(deps.storage, info.sender.clone())?;
     let config = CONFIG.load(deps.storage)?;
-    if info.sender != config.owner {
-        return Err(crate::ContractError::Unauthorized {});
-    }
 
     let change_msg = Cw721ExecuteMsg::<Extension>::ChangeMinter {
         new_minter: owner.clone().into()
@@ -778,14 +2310,41 @@ pub fn execute_change_cw721_owner(
         .add_submessages(vec![]))
 }
 
+pub fn execute_set_transfer_policy(
+    deps: DepsMut,
+    info: MessageInfo,
+    policy: Option<TransferPolicy>
+) -> Result<Response, crate::ContractError> {
+    marble_roles::check_role(deps.storage, &CONFIG, &info.sender, Role::RoyaltyManager)?;
+    let config = CONFIG.load(deps.storage)?;
+
+    let callback = CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: config.cw721_address.ok_or(crate::ContractError::Uninitialized {})?.to_string(),
+        msg: to_binary(&Cw721ExecuteMsg::<Extension>::SetTransferPolicy { policy: policy.clone() })?,
+        funds: vec![],
+    });
+
+    Ok(Response::new()
+        .add_message(callback)
+        .add_attribute("action", "set_transfer_policy")
+        .add_attribute("enforced", policy.is_some().to_string())
+    )
+}
+
 
 pub fn execute_update_unused_token_id(
     deps: DepsMut,
     info: MessageInfo,
     token_id: u32
 ) -> Result<Response, crate::ContractError> {
-    util::check_owner(deps.storage, info.sender.clone())?;
+    marble_roles::check_role(deps.storage, &CONFIG, &info.sender, Role::Minter)?;
     let mut config = CONFIG.load(deps.storage)?;
+    // ids below unused_token_id may already exist, so the counter can only skip ahead
+    if token_id < config.unused_token_id || token_id - 1 > config.max_tokens {
+        return Err(crate::ContractError::InvalidUnusedTokenId {});
+    }
+    let minted = util::minted_supply(deps.storage, &config)?;
+    MINTED_SUPPLY.save(deps.storage, &minted)?;
     config.unused_token_id = token_id;
     CONFIG.save(deps.storage, &config)?;
 
@@ -804,5 +2363,7 @@ pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, c
             previous_contract: version.contract,
         });
     }
+    util::migrate_sales(deps.storage)?;
+    util::start_escrow_rebuild(deps.storage)?;
     Ok(Response::default())
 }
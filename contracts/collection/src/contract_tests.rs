@@ -0,0 +1,3221 @@
+#![cfg(test)]
+use cosmwasm_std::{
+    coin, coins, from_binary, from_slice, to_binary, Addr, BankMsg, Binary, Coin, Deps, DepsMut,
+    Empty, Env, MessageInfo, Response, StdError, StdResult, Timestamp, Uint128, WasmMsg,
+};
+use cw20::Denom;
+use cw721::{NftInfoResponse, OwnerOfResponse};
+use cw721_base::{
+    msg::InstantiateMsg as Cw721InstantiateMsg, msg::MintMsg, msg::QueryMsg as Cw721QueryMsg,
+    ExecuteMsg as Cw721ExecuteMsg, Extension, FrozenResponse, ProvenanceResponse, TransferPolicy,
+    TransferPolicyResponse, TransferRule,
+};
+use cw_multi_test::{App, AppBuilder, Contract, ContractWrapper, Executor};
+use cw_storage_plus::Item;
+use cw_utils::Expiration;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::Digest;
+use wasmswap::msg::{
+    ExecuteMsg as WasmswapExecuteMsg, InfoResponse as WasmswapInfoResponse,
+    QueryMsg as WasmswapQueryMsg, Token1ForToken2PriceResponse, Token2ForToken1PriceResponse,
+    TokenSelect,
+};
+
+use crate::msg::{
+    Airdrop, AirdropResponse, ConfigResponse, Cw721WhitelistResponse, DenomTradeStats,
+    DurationType, EscrowBalance, EscrowBalancesResponse, EscrowCheck, ExecuteMsg, HasRoleResponse,
+    InstantiateMsg, IsClaimedResponse, MigrateMsg, MintPhase, MintPhasesResponse, MintedByResponse,
+    NftReceiveMsg, PauseScope, PausedResponse, PhaseMinted, QueryMsg, ReceiveMsg, RevealInfo, Role,
+    Royalty, RoyaltySource, SaleEdit, SaleInfo, SaleItem, SaleType, SupplyResponse, SupportedDenom,
+    SupportedDenomsResponse, SweepItem, SweepResponse, SweepResult, Trade, TradeStatsResponse,
+    TradesResponse, VerifyEscrowResponse,
+};
+use crate::state::{LEGACY_SALE, SALE};
+use crate::ContractError;
+
+const OWNER: &str = "owner";
+const SELLER: &str = "seller";
+const BUYER: &str = "buyer";
+
+const JUNO: &str = "ujuno";
+const ATOM: &str = "uatom";
+const OSMO: &str = "uosmo";
+
+// Minimal wasmswap pool trading at a fixed `token2_per_token1` rate
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+struct MockPoolInstantiateMsg {
+    token1_denom: String,
+    token2_denom: String,
+    token2_per_token1: u128,
+}
+
+const MOCK_POOL: Item<MockPoolInstantiateMsg> = Item::new("mock_pool");
+
+fn mock_pool_instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: MockPoolInstantiateMsg,
+) -> StdResult<Response> {
+    MOCK_POOL.save(deps.storage, &msg)?;
+    Ok(Response::new())
+}
+
+fn mock_pool_execute(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: WasmswapExecuteMsg,
+) -> StdResult<Response> {
+    let pool = MOCK_POOL.load(deps.storage)?;
+    match msg {
+        WasmswapExecuteMsg::Swap {
+            input_token,
+            input_amount,
+            min_output,
+            ..
+        } => {
+            let (input_denom, output_denom, output) = match input_token {
+                TokenSelect::Token1 => (
+                    pool.token1_denom,
+                    pool.token2_denom,
+                    input_amount * Uint128::from(pool.token2_per_token1),
+                ),
+                TokenSelect::Token2 => (
+                    pool.token2_denom,
+                    pool.token1_denom,
+                    input_amount / Uint128::from(pool.token2_per_token1),
+                ),
+            };
+            let paid = info
+                .funds
+                .iter()
+                .find(|c| c.denom == input_denom)
+                .map(|c| c.amount)
+                .unwrap_or_default();
+            if paid != input_amount {
+                return Err(StdError::generic_err("input amount mismatch"));
+            }
+            if output < min_output {
+                return Err(StdError::generic_err("min output not met"));
+            }
+            Ok(Response::new().add_message(BankMsg::Send {
+                to_address: info.sender.to_string(),
+                amount: coins(output.u128(), output_denom),
+            }))
+        }
+        _ => Err(StdError::generic_err("not supported")),
+    }
+}
+
+fn mock_pool_query(deps: Deps, _env: Env, msg: WasmswapQueryMsg) -> StdResult<Binary> {
+    let pool = MOCK_POOL.load(deps.storage)?;
+    let rate = Uint128::from(pool.token2_per_token1);
+    match msg {
+        WasmswapQueryMsg::Info {} => to_binary(&WasmswapInfoResponse {
+            token1_reserve: Uint128::zero(),
+            token1_denom: Denom::Native(pool.token1_denom),
+            token2_reserve: Uint128::zero(),
+            token2_denom: Denom::Native(pool.token2_denom),
+            lp_token_supply: Uint128::zero(),
+            lp_token_address: String::new(),
+        }),
+        WasmswapQueryMsg::Token1ForToken2Price { token1_amount } => {
+            to_binary(&Token1ForToken2PriceResponse {
+                token2_amount: token1_amount * rate,
+            })
+        }
+        WasmswapQueryMsg::Token2ForToken1Price { token2_amount } => {
+            to_binary(&Token2ForToken1PriceResponse {
+                token1_amount: token2_amount / rate,
+            })
+        }
+        _ => Err(StdError::generic_err("not supported")),
+    }
+}
+
+fn cw721_instantiate(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: cw721_base::InstantiateMsg,
+) -> StdResult<Response> {
+    cw721_base::Cw721MetadataContract::default().instantiate(deps, env, info, msg)
+}
+
+fn cw721_execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: Cw721ExecuteMsg<Extension>,
+) -> Result<Response, cw721_base::ContractError> {
+    cw721_base::Cw721MetadataContract::default().execute(deps, env, info, msg)
+}
+
+fn cw721_query(deps: Deps, env: Env, msg: Cw721QueryMsg) -> StdResult<Binary> {
+    cw721_base::Cw721MetadataContract::default().query(deps, env, msg)
+}
+
+fn contract_collection() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        crate::contract::execute,
+        crate::contract::instantiate,
+        crate::contract::query,
+    )
+    .with_reply(crate::contract::reply)
+    .with_migrate(crate::contract::migrate);
+    Box::new(contract)
+}
+
+fn contract_cw721() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(
+        cw721_execute,
+        cw721_instantiate,
+        cw721_query,
+    ))
+}
+
+fn contract_cw20() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(
+        cw20_base::contract::execute,
+        cw20_base::contract::instantiate,
+        cw20_base::contract::query,
+    ))
+}
+
+fn contract_mock_pool() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(
+        mock_pool_execute,
+        mock_pool_instantiate,
+        mock_pool_query,
+    ))
+}
+
+struct Suite {
+    app: App,
+    collection: Addr,
+    cw721: Addr,
+    atom_pool: Addr,
+    osmo_pool: Addr,
+}
+
+fn setup() -> Suite {
+    setup_with_shuffle(None)
+}
+
+fn setup_with_shuffle(shuffle_commit: Option<String>) -> Suite {
+    let mut app = AppBuilder::new().build(|router, _, storage| {
+        router
+            .bank
+            .init_balance(
+                storage,
+                &Addr::unchecked(OWNER),
+                vec![
+                    coin(1_000_000, JUNO),
+                    coin(1_000_000, ATOM),
+                    coin(1_000_000, OSMO),
+                ],
+            )
+            .unwrap();
+        router
+            .bank
+            .init_balance(
+                storage,
+                &Addr::unchecked(BUYER),
+                vec![coin(1_000, JUNO), coin(1_000, ATOM)],
+            )
+            .unwrap();
+    });
+
+    let cw721_id = app.store_code(contract_cw721());
+    let collection_id = app.store_code(contract_collection());
+    let pool_id = app.store_code(contract_mock_pool());
+
+    let collection = app
+        .instantiate_contract(
+            collection_id,
+            Addr::unchecked(OWNER),
+            &InstantiateMsg {
+                owner: Addr::unchecked(OWNER),
+                max_tokens: 100,
+                name: "Marble".to_string(),
+                symbol: "MRBL".to_string(),
+                token_code_id: cw721_id,
+                maximum_royalty_fee: 100_000,
+                royalties: vec![Royalty {
+                    address: Addr::unchecked(OWNER),
+                    rate: 50_000,
+                }],
+                uri: String::new(),
+                shuffle_commit,
+                index_traits: true,
+            },
+            &[],
+            "collection",
+            Some(OWNER.to_string()),
+        )
+        .unwrap();
+    let config: ConfigResponse = app
+        .wrap()
+        .query_wasm_smart(&collection, &QueryMsg::GetConfig {})
+        .unwrap();
+    let cw721 = config.cw721_address.unwrap();
+
+    // 1 ATOM = 10 JUNO, 1 OSMO = 2 JUNO
+    let mut pools = vec![];
+    for (denom, rate) in [(ATOM, 10u128), (OSMO, 2u128)] {
+        let pool = app
+            .instantiate_contract(
+                pool_id,
+                Addr::unchecked(OWNER),
+                &MockPoolInstantiateMsg {
+                    token1_denom: denom.to_string(),
+                    token2_denom: JUNO.to_string(),
+                    token2_per_token1: rate,
+                },
+                &[],
+                "pool",
+                None,
+            )
+            .unwrap();
+        app.send_tokens(
+            Addr::unchecked(OWNER),
+            pool.clone(),
+            &[coin(100_000, denom), coin(100_000, JUNO)],
+        )
+        .unwrap();
+        app.execute_contract(
+            Addr::unchecked(OWNER),
+            collection.clone(),
+            &ExecuteMsg::AddSupportedDenom {
+                denom: Denom::Native(denom.to_string()),
+                pool_address: Some(pool.clone()),
+                decimals: 6,
+                symbol: denom[1..].to_uppercase(),
+            },
+            &[],
+        )
+        .unwrap();
+        pools.push(pool);
+    }
+
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        collection.clone(),
+        &ExecuteMsg::AddSupportedDenom {
+            denom: Denom::Native(JUNO.to_string()),
+            pool_address: None,
+            decimals: 6,
+            symbol: "JUNO".to_string(),
+        },
+        &[],
+    )
+    .unwrap();
+
+    // free public mint for the rest of the day
+    let now = app.block_info().time.seconds();
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        collection.clone(),
+        &ExecuteMsg::AddMintPhase {
+            phase: MintPhase {
+                start: now,
+                end: now + 86_400,
+                price: Uint128::zero(),
+                denom: Denom::Native(JUNO.to_string()),
+                per_wallet_limit: None,
+                merkle_root: None,
+                soulbound: false,
+            },
+        },
+        &[],
+    )
+    .unwrap();
+
+    Suite {
+        app,
+        collection,
+        cw721,
+        atom_pool: pools[0].clone(),
+        osmo_pool: pools[1].clone(),
+    }
+}
+
+fn start_fixed_sale(suite: &mut Suite, price: u128, denom: &str) -> u32 {
+    mint(suite, SELLER, None, &[]).unwrap();
+    let config: ConfigResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(&suite.collection, &QueryMsg::GetConfig {})
+        .unwrap();
+    let token_id = config.unused_token_id - 1;
+
+    list_nft(
+        suite,
+        token_id,
+        &NftReceiveMsg::StartSale {
+            sale_type: SaleType::Fixed,
+            duration_type: DurationType::Fixed,
+            initial_price: Uint128::from(price),
+            reserve_price: Uint128::from(price),
+            denom: Denom::Native(denom.to_string()),
+        },
+    )
+    .unwrap();
+    token_id
+}
+
+fn balance(suite: &Suite, address: &str, denom: &str) -> u128 {
+    suite
+        .app
+        .wrap()
+        .query_balance(address, denom)
+        .unwrap()
+        .amount
+        .u128()
+}
+
+fn owner_of(suite: &Suite, token_id: u32) -> String {
+    let res: OwnerOfResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(
+            &suite.cw721,
+            &Cw721QueryMsg::OwnerOf {
+                token_id: token_id.to_string(),
+                include_expired: None,
+            },
+        )
+        .unwrap();
+    res.owner
+}
+
+fn list_nft(suite: &mut Suite, token_id: u32, msg: &NftReceiveMsg) -> Result<(), ContractError> {
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(SELLER),
+            suite.cw721.clone(),
+            &Cw721ExecuteMsg::<Extension>::SendNft {
+                contract: suite.collection.to_string(),
+                token_id: token_id.to_string(),
+                msg: to_binary(msg).unwrap(),
+            },
+            &[],
+        )
+        .map(|_| ())
+        .map_err(|err| err.downcast().unwrap())
+}
+
+#[test]
+fn manage_supported_denoms() {
+    let mut suite = setup();
+
+    let res: SupportedDenom = suite
+        .app
+        .wrap()
+        .query_wasm_smart(
+            &suite.collection,
+            &QueryMsg::GetSupportedDenom {
+                denom: Denom::Native(ATOM.to_string()),
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        res,
+        SupportedDenom {
+            denom: Denom::Native(ATOM.to_string()),
+            pool_address: Some(suite.atom_pool.clone()),
+            decimals: 6,
+            symbol: "ATOM".to_string(),
+            enabled: true,
+        }
+    );
+
+    // only the owner can manage the registry
+    let err = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(BUYER),
+            suite.collection.clone(),
+            &ExecuteMsg::RemoveSupportedDenom {
+                denom: Denom::Native(ATOM.to_string()),
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::Unauthorized {}
+    ));
+
+    let err = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            suite.collection.clone(),
+            &ExecuteMsg::AddSupportedDenom {
+                denom: Denom::Native(ATOM.to_string()),
+                pool_address: None,
+                decimals: 6,
+                symbol: "ATOM".to_string(),
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::DenomAlreadySupported {}
+    ));
+
+    // the pool must hold the denom it is registered for
+    let err = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            suite.collection.clone(),
+            &ExecuteMsg::UpdateSupportedDenom {
+                denom: Denom::Native(ATOM.to_string()),
+                pool_address: Some(suite.osmo_pool.clone()),
+                decimals: 6,
+                symbol: "ATOM".to_string(),
+                enabled: true,
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::PoolAndTokenMismatch {}
+    ));
+
+    let err = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            suite.collection.clone(),
+            &ExecuteMsg::UpdateSupportedDenom {
+                denom: Denom::Native("uscrt".to_string()),
+                pool_address: None,
+                decimals: 6,
+                symbol: "SCRT".to_string(),
+                enabled: true,
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::UnsupportedDenom {}
+    ));
+
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            suite.collection.clone(),
+            &ExecuteMsg::RemoveSupportedDenom {
+                denom: Denom::Native(ATOM.to_string()),
+            },
+            &[],
+        )
+        .unwrap();
+    let res: SupportedDenomsResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(
+            &suite.collection,
+            &QueryMsg::GetSupportedDenoms {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    let symbols: Vec<_> = res.list.into_iter().map(|d| d.symbol).collect();
+    assert_eq!(symbols, vec!["JUNO", "OSMO"]);
+}
+
+#[test]
+fn start_sale_requires_supported_denom() {
+    let mut suite = setup();
+    mint(&mut suite, SELLER, None, &[]).unwrap();
+
+    let start_sale = |denom: &str| NftReceiveMsg::StartSale {
+        sale_type: SaleType::Fixed,
+        duration_type: DurationType::Fixed,
+        initial_price: Uint128::from(100u128),
+        reserve_price: Uint128::from(100u128),
+        denom: Denom::Native(denom.to_string()),
+    };
+    let err = list_nft(&mut suite, 1, &start_sale("uscrt")).unwrap_err();
+    assert!(matches!(err, ContractError::UnsupportedDenom {}));
+
+    // disabled denoms cannot be listed in either
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            suite.collection.clone(),
+            &ExecuteMsg::UpdateSupportedDenom {
+                denom: Denom::Native(OSMO.to_string()),
+                pool_address: Some(suite.osmo_pool.clone()),
+                decimals: 6,
+                symbol: "OSMO".to_string(),
+                enabled: false,
+            },
+            &[],
+        )
+        .unwrap();
+    let err = list_nft(&mut suite, 1, &start_sale(OSMO)).unwrap_err();
+    assert!(matches!(err, ContractError::UnsupportedDenom {}));
+
+    list_nft(&mut suite, 1, &start_sale(JUNO)).unwrap();
+    assert_eq!(owner_of(&suite, 1), suite.collection.to_string());
+}
+
+#[test]
+fn propose_with_single_hop_swap() {
+    let mut suite = setup();
+    let token_id = start_fixed_sale(&mut suite, 100, JUNO);
+
+    // 10 ATOM -> 100 JUNO
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(BUYER),
+            suite.collection.clone(),
+            &ExecuteMsg::Propose {
+                cw721_address: None,
+                token_id: token_id.to_string(),
+                denom: ATOM.to_string(),
+                min_output: Some(Uint128::from(100u128)),
+            },
+            &coins(10, ATOM),
+        )
+        .unwrap();
+
+    assert_eq!(owner_of(&suite, token_id), BUYER);
+    assert_eq!(balance(&suite, BUYER, ATOM), 990);
+    assert_eq!(balance(&suite, SELLER, JUNO), 95);
+    assert_eq!(balance(&suite, suite.collection.as_str(), JUNO), 0);
+}
+
+#[test]
+fn propose_with_multi_hop_swap_refunds_excess() {
+    let mut suite = setup();
+    let token_id = start_fixed_sale(&mut suite, 100, OSMO);
+    let owner_osmo = balance(&suite, OWNER, OSMO);
+
+    // 25 ATOM -> 250 JUNO -> 125 OSMO, of which 25 OSMO is refunded
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(BUYER),
+            suite.collection.clone(),
+            &ExecuteMsg::Propose {
+                cw721_address: None,
+                token_id: token_id.to_string(),
+                denom: ATOM.to_string(),
+                min_output: Some(Uint128::from(120u128)),
+            },
+            &coins(25, ATOM),
+        )
+        .unwrap();
+
+    assert_eq!(owner_of(&suite, token_id), BUYER);
+    assert_eq!(balance(&suite, BUYER, ATOM), 975);
+    assert_eq!(balance(&suite, BUYER, OSMO), 25);
+    assert_eq!(balance(&suite, SELLER, OSMO), 95);
+    assert_eq!(balance(&suite, OWNER, OSMO), owner_osmo + 5);
+    assert_eq!(balance(&suite, suite.collection.as_str(), OSMO), 0);
+    assert_eq!(balance(&suite, suite.collection.as_str(), JUNO), 0);
+}
+
+#[test]
+fn propose_with_swap_checks_min_output() {
+    let mut suite = setup();
+    let token_id = start_fixed_sale(&mut suite, 100, OSMO);
+
+    let err = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(BUYER),
+            suite.collection.clone(),
+            &ExecuteMsg::Propose {
+                cw721_address: None,
+                token_id: token_id.to_string(),
+                denom: ATOM.to_string(),
+                min_output: Some(Uint128::from(200u128)),
+            },
+            &coins(25, ATOM),
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::SwapOutputTooLow { .. }
+    ));
+
+    // 10 ATOM only converts to 50 OSMO
+    let err = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(BUYER),
+            suite.collection.clone(),
+            &ExecuteMsg::Propose {
+                cw721_address: None,
+                token_id: token_id.to_string(),
+                denom: ATOM.to_string(),
+                min_output: None,
+            },
+            &coins(10, ATOM),
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::LowerPrice {}
+    ));
+
+    assert_eq!(owner_of(&suite, token_id), suite.collection.to_string());
+    assert_eq!(balance(&suite, BUYER, ATOM), 1_000);
+}
+
+#[test]
+fn propose_without_swap_route() {
+    let mut suite = setup();
+    let token_id = start_fixed_sale(&mut suite, 100, ATOM);
+
+    for denom in [ATOM, OSMO] {
+        suite
+            .app
+            .execute_contract(
+                Addr::unchecked(OWNER),
+                suite.collection.clone(),
+                &ExecuteMsg::RemoveSupportedDenom {
+                    denom: Denom::Native(denom.to_string()),
+                },
+                &[],
+            )
+            .unwrap();
+    }
+
+    let err = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(BUYER),
+            suite.collection.clone(),
+            &ExecuteMsg::Propose {
+                cw721_address: None,
+                token_id: token_id.to_string(),
+                denom: JUNO.to_string(),
+                min_output: None,
+            },
+            &coins(1_000, JUNO),
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::NoSwapRoute {}
+    ));
+}
+
+fn sha256(data: &[u8]) -> [u8; 32] {
+    sha2::Sha256::digest(data).into()
+}
+
+fn hash_pair(a: [u8; 32], b: [u8; 32]) -> [u8; 32] {
+    let mut pair = [a, b];
+    pair.sort_unstable();
+    sha256(&pair.concat())
+}
+
+fn mint(
+    suite: &mut Suite,
+    sender: &str,
+    proof: Option<Vec<String>>,
+    funds: &[Coin],
+) -> Result<(), ContractError> {
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(sender),
+            suite.collection.clone(),
+            &ExecuteMsg::Mint {
+                uri: "ipfs://token".to_string(),
+                extension: None,
+                proof,
+            },
+            funds,
+        )
+        .map(|_| ())
+        .map_err(|err| err.downcast().unwrap())
+}
+
+fn update_mint_phase(suite: &mut Suite, phase: MintPhase) {
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            suite.collection.clone(),
+            &ExecuteMsg::UpdateMintPhase { phase_id: 1, phase },
+            &[],
+        )
+        .unwrap();
+}
+
+#[test]
+fn mint_requires_active_phase() {
+    let mut suite = setup();
+    let now = suite.app.block_info().time.seconds();
+
+    // phases may not overlap
+    let err = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            suite.collection.clone(),
+            &ExecuteMsg::AddMintPhase {
+                phase: MintPhase {
+                    start: now + 3_600,
+                    end: now + 100_000,
+                    price: Uint128::zero(),
+                    denom: Denom::Native(JUNO.to_string()),
+                    per_wallet_limit: None,
+                    merkle_root: None,
+                    soulbound: false,
+                },
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::MintPhaseOverlap {}
+    ));
+
+    update_mint_phase(
+        &mut suite,
+        MintPhase {
+            start: now + 3_600,
+            end: now + 7_200,
+            price: Uint128::zero(),
+            denom: Denom::Native(JUNO.to_string()),
+            per_wallet_limit: None,
+            merkle_root: None,
+            soulbound: false,
+        },
+    );
+    let err = mint(&mut suite, BUYER, None, &[]).unwrap_err();
+    assert!(matches!(err, ContractError::NoActiveMintPhase {}));
+
+    suite
+        .app
+        .update_block(|block| block.time = block.time.plus_seconds(3_600));
+    mint(&mut suite, BUYER, None, &[]).unwrap();
+
+    suite
+        .app
+        .update_block(|block| block.time = block.time.plus_seconds(3_600));
+    let err = mint(&mut suite, BUYER, None, &[]).unwrap_err();
+    assert!(matches!(err, ContractError::NoActiveMintPhase {}));
+}
+
+#[test]
+fn paid_mint_phase() {
+    let mut suite = setup();
+    let now = suite.app.block_info().time.seconds();
+    update_mint_phase(
+        &mut suite,
+        MintPhase {
+            start: now,
+            end: now + 3_600,
+            price: Uint128::from(50u128),
+            denom: Denom::Native(JUNO.to_string()),
+            per_wallet_limit: Some(2),
+            merkle_root: None,
+            soulbound: false,
+        },
+    );
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            suite.collection.clone(),
+            &ExecuteMsg::UpdatePayoutAddress {
+                payout_address: Addr::unchecked("treasury"),
+            },
+            &[],
+        )
+        .unwrap();
+
+    let err = mint(&mut suite, BUYER, None, &[]).unwrap_err();
+    assert!(matches!(err, ContractError::NativeInputZero {}));
+    let err = mint(&mut suite, BUYER, None, &coins(40, JUNO)).unwrap_err();
+    assert!(matches!(err, ContractError::WrongPaymentAmount {}));
+    let err = mint(&mut suite, BUYER, None, &coins(60, JUNO)).unwrap_err();
+    assert!(matches!(err, ContractError::WrongPaymentAmount {}));
+    let err = mint(&mut suite, BUYER, None, &[coin(10, ATOM), coin(50, JUNO)]).unwrap_err();
+    assert!(matches!(err, ContractError::WrongPaymentAmount {}));
+
+    mint(&mut suite, BUYER, None, &coins(50, JUNO)).unwrap();
+    mint(&mut suite, BUYER, None, &coins(50, JUNO)).unwrap();
+    assert_eq!(balance(&suite, "treasury", JUNO), 100);
+    assert_eq!(balance(&suite, BUYER, JUNO), 900);
+    assert_eq!(owner_of(&suite, 2), BUYER);
+
+    let err = mint(&mut suite, BUYER, None, &coins(50, JUNO)).unwrap_err();
+    assert!(matches!(err, ContractError::WalletLimitExceeded {}));
+
+    let res: MintedByResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(
+            &suite.collection,
+            &QueryMsg::MintedBy {
+                address: Addr::unchecked(BUYER),
+            },
+        )
+        .unwrap();
+    assert_eq!(res.total, 2);
+    assert_eq!(
+        res.list,
+        vec![PhaseMinted {
+            phase_id: 1,
+            count: 2
+        }]
+    );
+
+    let res: MintPhasesResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(&suite.collection, &QueryMsg::MintPhases {})
+        .unwrap();
+    assert_eq!(res.payout_address, Addr::unchecked("treasury"));
+    assert_eq!(res.list.len(), 1);
+    assert_eq!(res.list[0].phase.price, Uint128::from(50u128));
+}
+
+#[test]
+fn allowlist_mint_phase() {
+    let mut suite = setup();
+    let now = suite.app.block_info().time.seconds();
+
+    let buyer_leaf = sha256(BUYER.as_bytes());
+    let friend_leaf = sha256(b"friend");
+    let other_leaf = sha256(b"other");
+    let pair = hash_pair(buyer_leaf, friend_leaf);
+    let root = hash_pair(pair, other_leaf);
+
+    update_mint_phase(
+        &mut suite,
+        MintPhase {
+            start: now,
+            end: now + 3_600,
+            price: Uint128::zero(),
+            denom: Denom::Native(JUNO.to_string()),
+            per_wallet_limit: Some(1),
+            merkle_root: Some(hex::encode(root)),
+            soulbound: false,
+        },
+    );
+
+    let proof = vec![hex::encode(friend_leaf), hex::encode(other_leaf)];
+
+    let err = mint(&mut suite, SELLER, Some(proof.clone()), &[]).unwrap_err();
+    assert!(matches!(err, ContractError::VerificationFailed {}));
+    let err = mint(&mut suite, BUYER, None, &[]).unwrap_err();
+    assert!(matches!(err, ContractError::VerificationFailed {}));
+    let err = mint(&mut suite, BUYER, Some(vec!["zz".to_string()]), &[]).unwrap_err();
+    assert!(matches!(err, ContractError::WrongLength {}));
+
+    mint(&mut suite, BUYER, Some(proof.clone()), &[]).unwrap();
+    assert_eq!(owner_of(&suite, 1), BUYER);
+
+    let err = mint(&mut suite, BUYER, Some(proof), &[]).unwrap_err();
+    assert!(matches!(err, ContractError::WalletLimitExceeded {}));
+}
+
+fn claim_airdrop(
+    suite: &mut Suite,
+    sender: &str,
+    index: u32,
+    amount: u32,
+    proof: Vec<String>,
+) -> Result<(), ContractError> {
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(sender),
+            suite.collection.clone(),
+            &ExecuteMsg::ClaimAirdrop {
+                index,
+                amount,
+                proof,
+            },
+            &[],
+        )
+        .map(|_| ())
+        .map_err(|err| err.downcast().unwrap())
+}
+
+fn is_claimed(suite: &Suite, index: u32) -> bool {
+    let res: IsClaimedResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(&suite.collection, &QueryMsg::IsClaimed { index })
+        .unwrap();
+    res.is_claimed
+}
+
+#[test]
+fn merkle_airdrop() {
+    let mut suite = setup();
+    let now = suite.app.block_info().time.seconds();
+
+    let buyer_leaf = sha256(format!("0:{}:2", BUYER).as_bytes());
+    let seller_leaf = sha256(format!("1:{}:1", SELLER).as_bytes());
+    let root = hash_pair(buyer_leaf, seller_leaf);
+    let airdrop = Airdrop {
+        merkle_root: hex::encode(root),
+        expiration: Expiration::AtTime(Timestamp::from_seconds(now + 3_600)),
+        token_uri: "ipfs://airdrop".to_string(),
+        extension: None,
+    };
+
+    let err = claim_airdrop(&mut suite, BUYER, 0, 2, vec![hex::encode(seller_leaf)]).unwrap_err();
+    assert!(matches!(err, ContractError::NoAirdrop {}));
+
+    let err = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(BUYER),
+            suite.collection.clone(),
+            &ExecuteMsg::RegisterAirdrop {
+                airdrop: airdrop.clone(),
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::Unauthorized {}
+    ));
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            suite.collection.clone(),
+            &ExecuteMsg::RegisterAirdrop {
+                airdrop: airdrop.clone(),
+            },
+            &[],
+        )
+        .unwrap();
+
+    let res: AirdropResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(&suite.collection, &QueryMsg::Airdrop {})
+        .unwrap();
+    assert_eq!(res.airdrop_id, 1);
+    assert_eq!(res.airdrop, Some(airdrop));
+
+    // the claimed amount is part of the leaf
+    let err = claim_airdrop(&mut suite, BUYER, 0, 3, vec![hex::encode(seller_leaf)]).unwrap_err();
+    assert!(matches!(err, ContractError::VerificationFailed {}));
+    let err = claim_airdrop(&mut suite, SELLER, 0, 2, vec![hex::encode(seller_leaf)]).unwrap_err();
+    assert!(matches!(err, ContractError::VerificationFailed {}));
+
+    assert!(!is_claimed(&suite, 0));
+    claim_airdrop(&mut suite, BUYER, 0, 2, vec![hex::encode(seller_leaf)]).unwrap();
+    assert!(is_claimed(&suite, 0));
+    assert!(!is_claimed(&suite, 1));
+    assert_eq!(owner_of(&suite, 1), BUYER);
+    assert_eq!(owner_of(&suite, 2), BUYER);
+
+    let err = claim_airdrop(&mut suite, BUYER, 0, 2, vec![hex::encode(seller_leaf)]).unwrap_err();
+    assert!(matches!(err, ContractError::Claimed {}));
+
+    suite
+        .app
+        .update_block(|block| block.time = block.time.plus_seconds(3_600));
+    let err = claim_airdrop(&mut suite, SELLER, 1, 1, vec![hex::encode(buyer_leaf)]).unwrap_err();
+    assert!(matches!(err, ContractError::AlreadyExpired {}));
+}
+
+#[test]
+fn shuffled_mint() {
+    let mut suite = setup_with_shuffle(Some(hex::encode(sha256(b"secret"))));
+    let config: ConfigResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(&suite.collection, &QueryMsg::GetConfig {})
+        .unwrap();
+    assert!(config.shuffle);
+
+    owner_execute(
+        &mut suite,
+        &ExecuteMsg::SetPlaceholderUri {
+            placeholder_uri: "ipfs://hidden".to_string(),
+        },
+    )
+    .unwrap();
+    batch_mint(&mut suite, 25).unwrap();
+
+    // nothing about the order is known while ids can still be minted
+    let seed_msg = ExecuteMsg::RevealShuffleSeed {
+        seed: "secret".to_string(),
+    };
+    let err = owner_execute(&mut suite, &seed_msg).unwrap_err();
+    assert!(matches!(err, ContractError::MintOngoing {}));
+    let reveal_msg = ExecuteMsg::Reveal {
+        base_uri: "ipfs://revealed/".to_string(),
+        provenance_hash: hex::encode(sha256(b"metadata")),
+    };
+    let err = owner_execute(&mut suite, &reveal_msg).unwrap_err();
+    assert!(matches!(err, ContractError::ShufflePending {}));
+
+    for _ in 0..3 {
+        batch_mint(&mut suite, 25).unwrap();
+    }
+    let err = mint(&mut suite, BUYER, None, &[]).unwrap_err();
+    assert!(matches!(err, ContractError::MaxTokensExceed {}));
+    // ids are handed out in order, the shuffle applies to the metadata
+    assert_eq!(owner_of(&suite, config.max_tokens), OWNER);
+    assert_eq!(token_uri(&suite, config.max_tokens), "ipfs://hidden");
+
+    let err = owner_execute(
+        &mut suite,
+        &ExecuteMsg::RevealShuffleSeed {
+            seed: "guess".to_string(),
+        },
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::VerificationFailed {}));
+    owner_execute(&mut suite, &seed_msg).unwrap();
+    let err = owner_execute(&mut suite, &seed_msg).unwrap_err();
+    assert!(matches!(err, ContractError::AlreadyRevealed {}));
+
+    owner_execute(&mut suite, &reveal_msg).unwrap();
+    for _ in 0..4 {
+        reveal_tokens(&mut suite, 30).unwrap();
+    }
+
+    // every metadata id is revealed exactly once, just not in order
+    let mut metadata_ids: Vec<u32> = (1..=config.max_tokens)
+        .map(|token_id| {
+            token_uri(&suite, token_id)
+                .strip_prefix("ipfs://revealed/")
+                .unwrap()
+                .parse()
+                .unwrap()
+        })
+        .collect();
+    let sequential: Vec<u32> = (1..=config.max_tokens).collect();
+    assert_ne!(metadata_ids, sequential);
+    metadata_ids.sort_unstable();
+    assert_eq!(metadata_ids, sequential);
+}
+
+fn token_uri(suite: &Suite, token_id: u32) -> String {
+    let res: NftInfoResponse<Extension> = suite
+        .app
+        .wrap()
+        .query_wasm_smart(
+            &suite.cw721,
+            &Cw721QueryMsg::NftInfo {
+                token_id: token_id.to_string(),
+            },
+        )
+        .unwrap();
+    res.token_uri.unwrap()
+}
+
+fn reveal_tokens(suite: &mut Suite, limit: u32) -> Result<(), ContractError> {
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(BUYER),
+            suite.collection.clone(),
+            &ExecuteMsg::RevealTokens { limit: Some(limit) },
+            &[],
+        )
+        .map(|_| ())
+        .map_err(|err| err.downcast().unwrap())
+}
+
+#[test]
+fn delayed_reveal() {
+    let mut suite = setup();
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            suite.collection.clone(),
+            &ExecuteMsg::SetPlaceholderUri {
+                placeholder_uri: "ipfs://hidden".to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+
+    mint(&mut suite, BUYER, None, &[]).unwrap();
+    mint(&mut suite, SELLER, None, &[]).unwrap();
+    assert_eq!(token_uri(&suite, 1), "ipfs://hidden");
+    assert_eq!(token_uri(&suite, 2), "ipfs://hidden");
+
+    let err = reveal_tokens(&mut suite, 10).unwrap_err();
+    assert!(matches!(err, ContractError::NotRevealed {}));
+
+    let reveal_msg = ExecuteMsg::Reveal {
+        base_uri: "ipfs://revealed/".to_string(),
+        provenance_hash: hex::encode(sha256(b"metadata")),
+    };
+    let err = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(BUYER),
+            suite.collection.clone(),
+            &reveal_msg,
+            &[],
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::Unauthorized {}
+    ));
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            suite.collection.clone(),
+            &reveal_msg,
+            &[],
+        )
+        .unwrap();
+    let err = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            suite.collection.clone(),
+            &reveal_msg,
+            &[],
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::AlreadyRevealed {}
+    ));
+
+    // tokens minted after the reveal get their final uri right away
+    mint(&mut suite, BUYER, None, &[]).unwrap();
+    assert_eq!(token_uri(&suite, 3), "ipfs://revealed/3");
+
+    reveal_tokens(&mut suite, 1).unwrap();
+    assert_eq!(token_uri(&suite, 1), "ipfs://revealed/1");
+    assert_eq!(token_uri(&suite, 2), "ipfs://hidden");
+
+    reveal_tokens(&mut suite, 10).unwrap();
+    assert_eq!(token_uri(&suite, 2), "ipfs://revealed/2");
+
+    let res: Option<RevealInfo> = suite
+        .app
+        .wrap()
+        .query_wasm_smart(&suite.collection, &QueryMsg::RevealInfo {})
+        .unwrap();
+    let res = res.unwrap();
+    assert_eq!(res.base_uri, Some("ipfs://revealed/".to_string()));
+    assert_eq!(res.provenance_hash, Some(hex::encode(sha256(b"metadata"))));
+    assert_eq!(res.unrevealed, 0);
+}
+
+#[test]
+fn freeze_waits_for_reveal() {
+    let mut suite = setup();
+    owner_execute(
+        &mut suite,
+        &ExecuteMsg::SetPlaceholderUri {
+            placeholder_uri: "ipfs://hidden".to_string(),
+        },
+    )
+    .unwrap();
+    mint(&mut suite, BUYER, None, &[]).unwrap();
+
+    // freezing now would strand the placeholder tokens
+    let err = owner_execute(&mut suite, &ExecuteMsg::FreezeMetadata { limit: None }).unwrap_err();
+    assert!(matches!(err, ContractError::RevealPending {}));
+    let err = owner_execute(
+        &mut suite,
+        &ExecuteMsg::SetTokenFrozen {
+            token_id: Some(1),
+            frozen: true,
+        },
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::RevealPending {}));
+
+    owner_execute(
+        &mut suite,
+        &ExecuteMsg::Reveal {
+            base_uri: "ipfs://revealed/".to_string(),
+            provenance_hash: hex::encode(sha256(b"metadata")),
+        },
+    )
+    .unwrap();
+    let err = owner_execute(&mut suite, &ExecuteMsg::FreezeMetadata { limit: None }).unwrap_err();
+    assert!(matches!(err, ContractError::RevealPending {}));
+
+    reveal_tokens(&mut suite, 10).unwrap();
+    owner_execute(&mut suite, &ExecuteMsg::FreezeMetadata { limit: None }).unwrap();
+    assert_eq!(token_uri(&suite, 1), "ipfs://revealed/1");
+}
+
+#[test]
+fn reveal_skips_burned_tokens() {
+    let mut suite = setup();
+    owner_execute(
+        &mut suite,
+        &ExecuteMsg::SetPlaceholderUri {
+            placeholder_uri: "ipfs://hidden".to_string(),
+        },
+    )
+    .unwrap();
+    mint(&mut suite, BUYER, None, &[]).unwrap();
+    mint(&mut suite, BUYER, None, &[]).unwrap();
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(BUYER),
+            suite.cw721.clone(),
+            &Cw721ExecuteMsg::<Extension>::Burn {
+                token_id: "1".to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+
+    owner_execute(
+        &mut suite,
+        &ExecuteMsg::Reveal {
+            base_uri: "ipfs://revealed/".to_string(),
+            provenance_hash: hex::encode(sha256(b"metadata")),
+        },
+    )
+    .unwrap();
+    reveal_tokens(&mut suite, 10).unwrap();
+    assert_eq!(token_uri(&suite, 2), "ipfs://revealed/2");
+    owner_execute(&mut suite, &ExecuteMsg::FreezeMetadata { limit: None }).unwrap();
+}
+
+#[test]
+fn edit_token_metadata() {
+    let mut suite = setup();
+    mint(&mut suite, BUYER, None, &[]).unwrap();
+
+    let edit_msg = ExecuteMsg::EditToken {
+        token_id: 1,
+        uri: "ipfs://edited".to_string(),
+        extension: None,
+    };
+    let err = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(BUYER),
+            suite.collection.clone(),
+            &edit_msg,
+            &[],
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::Unauthorized {}
+    ));
+
+    let res = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            suite.collection.clone(),
+            &edit_msg,
+            &[],
+        )
+        .unwrap();
+    assert_eq!(token_uri(&suite, 1), "ipfs://edited");
+
+    let event = res
+        .events
+        .iter()
+        .find(|event| event.ty == "wasm-edit_token")
+        .unwrap();
+    let hash = |uri: &str| {
+        let info = NftInfoResponse::<Extension> {
+            token_uri: Some(uri.to_string()),
+            extension: None,
+        };
+        hex::encode(sha256(to_binary(&info).unwrap().as_slice()))
+    };
+    let attr = |key: &str| {
+        event
+            .attributes
+            .iter()
+            .find(|attr| attr.key == key)
+            .unwrap()
+            .value
+            .clone()
+    };
+    assert_eq!(attr("before_hash"), hash("ipfs://token"));
+    assert_eq!(attr("after_hash"), hash("ipfs://edited"));
+
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            suite.collection.clone(),
+            &ExecuteMsg::SetTokenFrozen {
+                token_id: Some(1),
+                frozen: true,
+            },
+            &[],
+        )
+        .unwrap();
+    let err = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            suite.collection.clone(),
+            &edit_msg,
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<cw721_base::ContractError>().unwrap(),
+        cw721_base::ContractError::Frozen {}
+    );
+}
+
+#[test]
+fn freeze_collection_metadata() {
+    let mut suite = setup();
+    mint(&mut suite, BUYER, None, &[]).unwrap();
+
+    let res: FrozenResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(&suite.collection, &QueryMsg::MetadataFrozen {})
+        .unwrap();
+    assert!(!res.frozen);
+
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            suite.collection.clone(),
+            &ExecuteMsg::FreezeMetadata { limit: None },
+            &[],
+        )
+        .unwrap();
+
+    let res: FrozenResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(&suite.collection, &QueryMsg::MetadataFrozen {})
+        .unwrap();
+    assert!(res.frozen);
+    let res: ProvenanceResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(&suite.collection, &QueryMsg::Provenance {})
+        .unwrap();
+    assert_eq!(
+        res.provenance_hash,
+        Some(hex::encode(sha256(b"1:ipfs://token\n")))
+    );
+
+    let err = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(BUYER),
+            suite.collection.clone(),
+            &ExecuteMsg::Mint {
+                uri: "ipfs://token".to_string(),
+                extension: None,
+                proof: None,
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<cw721_base::ContractError>().unwrap(),
+        cw721_base::ContractError::MetadataFrozen {}
+    );
+}
+
+fn batch_mint(suite: &mut Suite, count: usize) -> Result<(), ContractError> {
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            suite.collection.clone(),
+            &ExecuteMsg::BatchMint {
+                uri: vec!["ipfs://token".to_string(); count],
+                extension: vec![None; count],
+                owner: vec![OWNER.to_string(); count],
+            },
+            &[],
+        )
+        .map(|_| ())
+        .map_err(|err| err.downcast().unwrap())
+}
+
+fn supply(suite: &Suite) -> SupplyResponse {
+    suite
+        .app
+        .wrap()
+        .query_wasm_smart(&suite.collection, &QueryMsg::Supply {})
+        .unwrap()
+}
+
+#[test]
+fn supply_accounting() {
+    let mut suite = setup();
+    mint(&mut suite, BUYER, None, &[]).unwrap();
+    mint(&mut suite, BUYER, None, &[]).unwrap();
+
+    // the counter can skip ids but never hand out existing ones again
+    let update_unused_token_id = |suite: &mut Suite, token_id: u32| {
+        suite.app.execute_contract(
+            Addr::unchecked(OWNER),
+            suite.collection.clone(),
+            &ExecuteMsg::UpdateUnusedTokenId { token_id },
+            &[],
+        )
+    };
+    let err = update_unused_token_id(&mut suite, 1).unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::InvalidUnusedTokenId {}
+    ));
+    let err = update_unused_token_id(&mut suite, 102).unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::InvalidUnusedTokenId {}
+    ));
+    update_unused_token_id(&mut suite, 11).unwrap();
+    assert_eq!(
+        supply(&suite),
+        SupplyResponse {
+            max_tokens: 100,
+            minted: 2,
+            burned: 0,
+            remaining: 90,
+        }
+    );
+
+    // every token of a batch counts against the cap
+    let err = batch_mint(&mut suite, 91).unwrap_err();
+    assert!(matches!(err, ContractError::MaxTokensExceed {}));
+    batch_mint(&mut suite, 90).unwrap();
+    assert_eq!(owner_of(&suite, 100), OWNER);
+    let err = batch_mint(&mut suite, 1).unwrap_err();
+    assert!(matches!(err, ContractError::MaxTokensExceed {}));
+
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(BUYER),
+            suite.cw721.clone(),
+            &Cw721ExecuteMsg::<Extension>::Burn {
+                token_id: "1".to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(
+        supply(&suite),
+        SupplyResponse {
+            max_tokens: 100,
+            minted: 92,
+            burned: 1,
+            remaining: 0,
+        }
+    );
+}
+
+fn ids(token_ids: &[u32]) -> Vec<String> {
+    token_ids
+        .iter()
+        .map(|token_id| token_id.to_string())
+        .collect()
+}
+
+fn fixed_sale_item(token_id: u32, price: u128) -> SaleItem {
+    SaleItem {
+        cw721_address: None,
+        token_id: token_id.to_string(),
+        sale_type: SaleType::Fixed,
+        duration_type: DurationType::Fixed,
+        initial_price: Uint128::from(price),
+        reserve_price: Uint128::from(price),
+        denom: Denom::Native(JUNO.to_string()),
+    }
+}
+
+#[test]
+fn batch_sales_and_sweep_buy() {
+    let mut suite = setup();
+    for _ in 0..3 {
+        mint(&mut suite, SELLER, None, &[]).unwrap();
+    }
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(SELLER),
+            suite.cw721.clone(),
+            &Cw721ExecuteMsg::<Extension>::ApproveAll {
+                operator: suite.collection.to_string(),
+                expires: None,
+            },
+            &[],
+        )
+        .unwrap();
+
+    let items: Vec<SaleItem> = (1..=3).map(|id| fixed_sale_item(id, 100)).collect();
+    let err = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(BUYER),
+            suite.collection.clone(),
+            &ExecuteMsg::BatchStartSale {
+                items: items.clone(),
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::Unauthorized {}
+    ));
+    let err = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(SELLER),
+            suite.collection.clone(),
+            &ExecuteMsg::BatchStartSale { items: vec![] },
+            &[],
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::InvalidBatchSize {}
+    ));
+
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(SELLER),
+            suite.collection.clone(),
+            &ExecuteMsg::BatchStartSale { items },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(owner_of(&suite, 1), suite.collection.to_string());
+    assert_eq!(owner_of(&suite, 3), suite.collection.to_string());
+
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(SELLER),
+            suite.collection.clone(),
+            &ExecuteMsg::BatchEditSale {
+                items: vec![fixed_sale_item(2, 150)],
+            },
+            &[],
+        )
+        .unwrap();
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(SELLER),
+            suite.collection.clone(),
+            &ExecuteMsg::BatchCancelSale {
+                cw721_address: None,
+                token_ids: ids(&[3]),
+            },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(owner_of(&suite, 3), SELLER);
+
+    let sweep = |suite: &mut Suite, token_ids: Vec<u32>, amount: u128| {
+        suite.app.execute_contract(
+            Addr::unchecked(BUYER),
+            suite.collection.clone(),
+            &ExecuteMsg::SweepBuy {
+                cw721_address: None,
+                token_ids: ids(&token_ids),
+            },
+            &coins(amount, JUNO),
+        )
+    };
+    let err = sweep(&mut suite, vec![1, 2], 200).unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::WrongPaymentAmount {}
+    ));
+    let err = sweep(&mut suite, vec![1, 3], 200).unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::NotOnSale {}
+    ));
+
+    sweep(&mut suite, vec![1, 2], 250).unwrap();
+    assert_eq!(owner_of(&suite, 1), BUYER);
+    assert_eq!(owner_of(&suite, 2), BUYER);
+    assert_eq!(balance(&suite, BUYER, JUNO), 750);
+    // 5% royalty goes to the owner
+    assert_eq!(balance(&suite, SELLER, JUNO), 238);
+}
+
+fn list_fixed(suite: &mut Suite, token_id: u32, price: u128, denom: Denom) {
+    list_nft(
+        suite,
+        token_id,
+        &NftReceiveMsg::StartSale {
+            sale_type: SaleType::Fixed,
+            duration_type: DurationType::Fixed,
+            initial_price: Uint128::from(price),
+            reserve_price: Uint128::from(price),
+            denom,
+        },
+    )
+    .unwrap();
+}
+
+#[test]
+fn sweep_with_budget() {
+    let mut suite = setup();
+    for _ in 0..5 {
+        mint(&mut suite, SELLER, None, &[]).unwrap();
+    }
+    list_fixed(&mut suite, 1, 100, Denom::Native(JUNO.to_string()));
+    list_fixed(&mut suite, 2, 10, Denom::Native(ATOM.to_string()));
+    list_fixed(&mut suite, 4, 300, Denom::Native(JUNO.to_string()));
+    list_fixed(&mut suite, 5, 50, Denom::Native(JUNO.to_string()));
+
+    let res = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(BUYER),
+            suite.collection.clone(),
+            &ExecuteMsg::Sweep {
+                cw721_address: None,
+                token_ids: ids(&[3, 1, 2, 4, 5]),
+                max_total: Uint128::from(350u128),
+            },
+            &coins(500, JUNO),
+        )
+        .unwrap();
+    let res: SweepResponse = from_binary(&res.data.unwrap()).unwrap();
+    assert_eq!(
+        res,
+        SweepResponse {
+            spent: Uint128::from(100u128),
+            refund: Uint128::from(400u128),
+            list: vec![
+                SweepItem {
+                    token_id: 3.to_string(),
+                    result: SweepResult::NotOnSale
+                },
+                SweepItem {
+                    token_id: 1.to_string(),
+                    result: SweepResult::Bought {
+                        price: Uint128::from(100u128)
+                    }
+                },
+                SweepItem {
+                    token_id: 2.to_string(),
+                    result: SweepResult::DenomMismatch
+                },
+                SweepItem {
+                    token_id: 4.to_string(),
+                    result: SweepResult::OverBudget
+                },
+                // the sweep stops at the first listing over budget
+                SweepItem {
+                    token_id: 5.to_string(),
+                    result: SweepResult::OverBudget
+                },
+            ],
+        }
+    );
+    assert_eq!(owner_of(&suite, 1), BUYER);
+    assert_eq!(owner_of(&suite, 5), suite.collection.to_string());
+    assert_eq!(balance(&suite, BUYER, JUNO), 900);
+}
+
+#[test]
+fn sweep_with_cw20() {
+    let mut suite = setup();
+    let cw20_id = suite.app.store_code(contract_cw20());
+    let cw20 = suite
+        .app
+        .instantiate_contract(
+            cw20_id,
+            Addr::unchecked(OWNER),
+            &cw20_base::msg::InstantiateMsg {
+                name: "Marble".to_string(),
+                symbol: "MRBL".to_string(),
+                decimals: 6,
+                initial_balances: vec![cw20::Cw20Coin {
+                    address: BUYER.to_string(),
+                    amount: Uint128::from(1_000u128),
+                }],
+                mint: None,
+                marketing: None,
+            },
+            &[],
+            "cw20",
+            None,
+        )
+        .unwrap();
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            suite.collection.clone(),
+            &ExecuteMsg::AddSupportedDenom {
+                denom: Denom::Cw20(cw20.clone()),
+                pool_address: None,
+                decimals: 6,
+                symbol: "MRBL".to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+
+    mint(&mut suite, SELLER, None, &[]).unwrap();
+    mint(&mut suite, SELLER, None, &[]).unwrap();
+    list_fixed(&mut suite, 1, 100, Denom::Cw20(cw20.clone()));
+    list_fixed(&mut suite, 2, 100, Denom::Native(JUNO.to_string()));
+
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(BUYER),
+            cw20.clone(),
+            &cw20::Cw20ExecuteMsg::Send {
+                contract: suite.collection.to_string(),
+                amount: Uint128::from(250u128),
+                msg: to_binary(&ReceiveMsg::Sweep {
+                    cw721_address: None,
+                    token_ids: ids(&[1, 2]),
+                    max_total: Uint128::from(1_000u128),
+                })
+                .unwrap(),
+            },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(owner_of(&suite, 1), BUYER);
+    assert_eq!(owner_of(&suite, 2), suite.collection.to_string());
+
+    let res: cw20::BalanceResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(
+            &cw20,
+            &cw20::Cw20QueryMsg::Balance {
+                address: BUYER.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(res.balance, Uint128::from(900u128));
+}
+
+fn edit_sale(suite: &mut Suite, item: SaleItem) -> Result<(), ContractError> {
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(SELLER),
+            suite.collection.clone(),
+            &ExecuteMsg::EditSale {
+                cw721_address: None,
+                token_id: item.token_id,
+                sale_type: item.sale_type,
+                duration_type: item.duration_type,
+                initial_price: item.initial_price,
+                reserve_price: item.reserve_price,
+                denom: item.denom,
+            },
+            &[],
+        )
+        .map(|_| ())
+        .map_err(|err| err.downcast().unwrap())
+}
+
+#[test]
+fn edit_sale_validates_and_keeps_history() {
+    let mut suite = setup();
+    let token_id = start_fixed_sale(&mut suite, 100, JUNO);
+
+    let mut item = fixed_sale_item(token_id, 100);
+    item.duration_type = DurationType::Bid(3);
+    let err = edit_sale(&mut suite, item).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidSaleType {}));
+
+    let mut item = fixed_sale_item(token_id, 100);
+    item.sale_type = SaleType::Auction;
+    item.duration_type = DurationType::Time(200, 100);
+    let err = edit_sale(&mut suite, item).unwrap_err();
+    assert!(matches!(err, ContractError::DurationIncorrect {}));
+
+    let mut item = fixed_sale_item(token_id, 100);
+    item.denom = Denom::Native("uscrt".to_string());
+    let err = edit_sale(&mut suite, item).unwrap_err();
+    assert!(matches!(err, ContractError::UnsupportedDenom {}));
+
+    let edited_at = suite.app.block_info().time.seconds();
+    let mut item = fixed_sale_item(token_id, 80);
+    item.denom = Denom::Native(ATOM.to_string());
+    edit_sale(&mut suite, item).unwrap();
+
+    let sale: SaleInfo = suite
+        .app
+        .wrap()
+        .query_wasm_smart(
+            &suite.collection,
+            &QueryMsg::GetSale {
+                cw721_address: None,
+                token_id: token_id.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(sale.denom, Denom::Native(ATOM.to_string()));
+    assert_eq!(sale.initial_price, Uint128::from(80u128));
+    assert_eq!(
+        sale.edits,
+        vec![SaleEdit {
+            time: edited_at,
+            sale_type: SaleType::Fixed,
+            duration_type: DurationType::Fixed,
+            initial_price: Uint128::from(100u128),
+            reserve_price: Uint128::from(100u128),
+            denom: Denom::Native(JUNO.to_string()),
+        }]
+    );
+
+    // only the latest edits are kept
+    for price in 81..93 {
+        edit_sale(&mut suite, fixed_sale_item(token_id, price)).unwrap();
+    }
+    let sale: SaleInfo = suite
+        .app
+        .wrap()
+        .query_wasm_smart(
+            &suite.collection,
+            &QueryMsg::GetSale {
+                cw721_address: None,
+                token_id: token_id.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(sale.edits.len(), 10);
+    assert_eq!(sale.edits[0].initial_price, Uint128::from(82u128));
+    assert_eq!(sale.edits[9].initial_price, Uint128::from(91u128));
+
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            suite.collection.clone(),
+            &ExecuteMsg::UpdateEnabled { enabled: false },
+            &[],
+        )
+        .unwrap();
+    let err = edit_sale(&mut suite, fixed_sale_item(token_id, 90)).unwrap_err();
+    assert!(matches!(err, ContractError::Disabled {}));
+}
+
+fn start_auction(suite: &mut Suite, duration_type: DurationType, reserve_price: u128) -> u32 {
+    mint(suite, SELLER, None, &[]).unwrap();
+    let config: ConfigResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(&suite.collection, &QueryMsg::GetConfig {})
+        .unwrap();
+    let token_id = config.unused_token_id - 1;
+    list_nft(
+        suite,
+        token_id,
+        &NftReceiveMsg::StartSale {
+            sale_type: SaleType::Auction,
+            duration_type,
+            initial_price: Uint128::from(50u128),
+            reserve_price: Uint128::from(reserve_price),
+            denom: Denom::Native(JUNO.to_string()),
+        },
+    )
+    .unwrap();
+    token_id
+}
+
+fn bid(suite: &mut Suite, sender: &str, token_id: u32, price: u128) {
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(sender),
+            suite.collection.clone(),
+            &ExecuteMsg::Propose {
+                cw721_address: None,
+                token_id: token_id.to_string(),
+                denom: JUNO.to_string(),
+                min_output: None,
+            },
+            &coins(price, JUNO),
+        )
+        .unwrap();
+}
+
+fn cancel_bid(
+    suite: &mut Suite,
+    sender: &str,
+    token_id: u32,
+    bid_id: u32,
+) -> Result<(), ContractError> {
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(sender),
+            suite.collection.clone(),
+            &ExecuteMsg::CancelPropose {
+                cw721_address: None,
+                token_id: token_id.to_string(),
+                bid_id,
+            },
+            &[],
+        )
+        .map(|_| ())
+        .map_err(|err| err.downcast().unwrap())
+}
+
+fn sale(suite: &Suite, token_id: u32) -> SaleInfo {
+    suite
+        .app
+        .wrap()
+        .query_wasm_smart(
+            &suite.collection,
+            &QueryMsg::GetSale {
+                cw721_address: None,
+                token_id: token_id.to_string(),
+            },
+        )
+        .unwrap()
+}
+
+#[test]
+fn cancel_propose_refunds_each_bid() {
+    let mut suite = setup();
+    let token_id = start_auction(&mut suite, DurationType::Fixed, 1_000);
+    bid(&mut suite, BUYER, token_id, 100);
+    bid(&mut suite, BUYER, token_id, 120);
+    bid(&mut suite, OWNER, token_id, 150);
+    assert_eq!(balance(&suite, BUYER, JUNO), 780);
+
+    let err = cancel_bid(&mut suite, BUYER, token_id, 4).unwrap_err();
+    assert!(matches!(err, ContractError::BidNotFound {}));
+    let err = cancel_bid(&mut suite, SELLER, token_id, 1).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+
+    cancel_bid(&mut suite, BUYER, token_id, 1).unwrap();
+    assert_eq!(balance(&suite, BUYER, JUNO), 880);
+    cancel_bid(&mut suite, BUYER, token_id, 2).unwrap();
+    assert_eq!(balance(&suite, BUYER, JUNO), 1_000);
+    let err = cancel_bid(&mut suite, BUYER, token_id, 2).unwrap_err();
+    assert!(matches!(err, ContractError::BidNotFound {}));
+
+    // the leading bid is below the reserve, so it can leave too
+    cancel_bid(&mut suite, OWNER, token_id, 3).unwrap();
+    assert!(sale(&suite, token_id).requests.is_empty());
+
+    // ids are not reused after withdrawals
+    bid(&mut suite, BUYER, token_id, 100);
+    assert_eq!(sale(&suite, token_id).requests[0].id, 4);
+}
+
+#[test]
+fn cancel_propose_keeps_leading_bid() {
+    let mut suite = setup();
+    let token_id = start_auction(&mut suite, DurationType::Fixed, 150);
+    bid(&mut suite, BUYER, token_id, 100);
+    bid(&mut suite, OWNER, token_id, 200);
+    assert!(sale(&suite, token_id).can_accept);
+
+    let err = cancel_bid(&mut suite, OWNER, token_id, 2).unwrap_err();
+    assert!(matches!(err, ContractError::LeadingBidLocked {}));
+    cancel_bid(&mut suite, BUYER, token_id, 1).unwrap();
+    assert!(sale(&suite, token_id).can_accept);
+
+    let now = suite.app.block_info().time.seconds();
+    let token_id = start_auction(&mut suite, DurationType::Time(now, now + 100), 150);
+    bid(&mut suite, BUYER, token_id, 100);
+    bid(&mut suite, OWNER, token_id, 200);
+
+    let err = cancel_bid(&mut suite, OWNER, token_id, 2).unwrap_err();
+    assert!(matches!(err, ContractError::LeadingBidLocked {}));
+
+    // the auction ending doesn't free a bid the seller can still accept
+    suite
+        .app
+        .update_block(|block| block.time = block.time.plus_seconds(101));
+    let err = cancel_bid(&mut suite, OWNER, token_id, 2).unwrap_err();
+    assert!(matches!(err, ContractError::LeadingBidLocked {}));
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(SELLER),
+            suite.collection.clone(),
+            &ExecuteMsg::AcceptSale {
+                cw721_address: None,
+                token_id: token_id.to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(owner_of(&suite, token_id), OWNER);
+
+    // a leading bid under the reserve is only held until the end
+    let now = suite.app.block_info().time.seconds();
+    let token_id = start_auction(&mut suite, DurationType::Time(now, now + 100), 500);
+    bid(&mut suite, BUYER, token_id, 100);
+    let err = cancel_bid(&mut suite, BUYER, token_id, 1).unwrap_err();
+    assert!(matches!(err, ContractError::LeadingBidLocked {}));
+    suite
+        .app
+        .update_block(|block| block.time = block.time.plus_seconds(101));
+    cancel_bid(&mut suite, BUYER, token_id, 1).unwrap();
+    assert!(sale(&suite, token_id).requests.is_empty());
+}
+
+fn escrow(suite: &Suite) -> Vec<EscrowBalance> {
+    let res: EscrowBalancesResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(&suite.collection, &QueryMsg::EscrowBalances {})
+        .unwrap();
+    res.list
+}
+
+fn verify_escrow(suite: &Suite) -> StdResult<VerifyEscrowResponse> {
+    suite
+        .app
+        .wrap()
+        .query_wasm_smart(&suite.collection, &QueryMsg::VerifyEscrow {})
+}
+
+#[test]
+fn escrow_ledger_follows_bids() {
+    let mut suite = setup();
+    let token_id = start_auction(&mut suite, DurationType::Fixed, 1_000);
+    bid(&mut suite, BUYER, token_id, 100);
+    bid(&mut suite, OWNER, token_id, 150);
+    assert_eq!(
+        escrow(&suite),
+        vec![EscrowBalance {
+            denom: Denom::Native(JUNO.to_string()),
+            amount: Uint128::from(250u128),
+        }]
+    );
+
+    cancel_bid(&mut suite, BUYER, token_id, 1).unwrap();
+    assert_eq!(escrow(&suite)[0].amount, Uint128::from(150u128));
+
+    let res = verify_escrow(&suite).unwrap();
+    assert!(res.solvent);
+    assert_eq!(
+        res.list,
+        vec![EscrowCheck {
+            denom: Denom::Native(JUNO.to_string()),
+            escrow: Uint128::from(150u128),
+            balance: Uint128::from(150u128),
+        }]
+    );
+
+    // funds leaving the contract behind the ledger's back show up as insolvency
+    let collection = suite.collection.clone();
+    suite.app.init_modules(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &collection, coins(100, JUNO))
+            .unwrap()
+    });
+    assert!(!verify_escrow(&suite).unwrap().solvent);
+    suite.app.init_modules(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &collection, coins(150, JUNO))
+            .unwrap()
+    });
+
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(SELLER),
+            suite.collection.clone(),
+            &ExecuteMsg::AcceptSale {
+                cw721_address: None,
+                token_id: token_id.to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(escrow(&suite)[0].amount, Uint128::zero());
+    assert!(verify_escrow(&suite).unwrap().solvent);
+}
+
+#[test]
+fn rebuilding_escrow_in_steps() {
+    let mut suite = setup();
+    let first = start_auction(&mut suite, DurationType::Fixed, 1_000);
+    let second = start_auction(&mut suite, DurationType::Fixed, 1_000);
+    bid(&mut suite, BUYER, first, 100);
+    bid(&mut suite, BUYER, second, 200);
+
+    let err = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(BUYER),
+            suite.collection.clone(),
+            &ExecuteMsg::RebuildEscrow { limit: None },
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap().to_string(),
+        ContractError::Unauthorized {}.to_string()
+    );
+
+    // a zero limit still counts one sale instead of ending the rebuild early
+    owner_execute(&mut suite, &ExecuteMsg::RebuildEscrow { limit: Some(0) }).unwrap();
+    assert_eq!(escrow(&suite)[0].amount, Uint128::from(100u128));
+    assert!(verify_escrow(&suite).unwrap().rebuilding);
+
+    // bids on counted sales go to the ledger, the rest are left to the rebuild
+    bid(&mut suite, OWNER, first, 150);
+    bid(&mut suite, OWNER, second, 250);
+    assert_eq!(escrow(&suite)[0].amount, Uint128::from(250u128));
+
+    // and refunds keep working on both sides of the cursor
+    cancel_bid(&mut suite, BUYER, first, 1).unwrap();
+    cancel_bid(&mut suite, BUYER, second, 1).unwrap();
+    assert_eq!(escrow(&suite)[0].amount, Uint128::from(150u128));
+
+    owner_execute(&mut suite, &ExecuteMsg::RebuildEscrow { limit: Some(1) }).unwrap();
+    let res = verify_escrow(&suite).unwrap();
+    assert!(!res.rebuilding);
+    assert!(res.solvent);
+    assert_eq!(escrow(&suite)[0].amount, Uint128::from(400u128));
+}
+
+// Writes a key of the collection's storage directly, under the prefixes cw-multi-test gives it
+fn set_collection_raw(suite: &mut Suite, key: &[u8], value: Option<&[u8]>) {
+    let namespace = [&b"contract_data/"[..], suite.collection.as_bytes()].concat();
+    let mut full_key = vec![];
+    for prefix in [&b"wasm"[..], &namespace[..]] {
+        full_key.extend_from_slice(&(prefix.len() as u16).to_be_bytes());
+        full_key.extend_from_slice(prefix);
+    }
+    full_key.extend_from_slice(key);
+    suite.app.init_modules(|_, _, storage| match value {
+        Some(value) => storage.set(&full_key, value),
+        None => storage.remove(&full_key),
+    });
+}
+
+#[test]
+fn migrate_moves_legacy_sales() {
+    let mut suite = setup();
+    let token_id = start_auction(&mut suite, DurationType::Fixed, 50);
+    bid(&mut suite, BUYER, token_id, 100);
+
+    // rewrite the sale as the baseline contract stored it: keyed by token id alone, a numeric
+    // token id and no cw721 address or bid ids
+    let legacy = format!(
+        r#"{{"token_id":{},"provider":"{}","sale_type":"Auction","duration_type":"Fixed","initial_price":"50","reserve_price":"50","requests":[{{"address":"{}","price":"100"}}],"denom":{{"native":"{}"}},"can_accept":true}}"#,
+        token_id, SELLER, BUYER, JUNO
+    );
+    let sale_key = SALE.key((&suite.cw721, token_id.to_string()));
+    set_collection_raw(&mut suite, &sale_key, None);
+    let legacy_key = LEGACY_SALE.key(token_id.to_string());
+    set_collection_raw(&mut suite, &legacy_key, Some(legacy.as_bytes()));
+
+    let code_id = suite.app.store_code(contract_collection());
+    suite
+        .app
+        .migrate_contract(
+            Addr::unchecked(OWNER),
+            suite.collection.clone(),
+            &MigrateMsg {},
+            code_id,
+        )
+        .unwrap();
+
+    assert!(suite
+        .app
+        .wrap()
+        .query_wasm_raw(&suite.collection, legacy_key.to_vec())
+        .unwrap()
+        .is_none());
+    let migrated = sale(&suite, token_id);
+    assert_eq!(migrated.token_id, token_id.to_string());
+    assert_eq!(migrated.cw721_address, Some(suite.cw721.clone()));
+    assert_eq!(migrated.requests[0].address, Addr::unchecked(BUYER));
+
+    // the ledger starts over empty and is rebuilt from the migrated sales
+    assert!(escrow(&suite).is_empty());
+    assert!(verify_escrow(&suite).unwrap().rebuilding);
+    owner_execute(&mut suite, &ExecuteMsg::RebuildEscrow { limit: None }).unwrap();
+    let res = verify_escrow(&suite).unwrap();
+    assert!(!res.rebuilding);
+    assert!(res.solvent);
+    assert_eq!(escrow(&suite)[0].amount, Uint128::from(100u128));
+
+    // and the migrated sale settles into a trade of the recorded contract
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(SELLER),
+            suite.collection.clone(),
+            &ExecuteMsg::AcceptSale {
+                cw721_address: None,
+                token_id: token_id.to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(owner_of(&suite, token_id), BUYER);
+    assert_eq!(escrow(&suite)[0].amount, Uint128::zero());
+    let res = trades(
+        &suite,
+        &QueryMsg::TradesByToken {
+            cw721_address: None,
+            token_id: token_id.to_string(),
+            start_after: None,
+            limit: None,
+        },
+    );
+    assert_eq!(res.len(), 1);
+    assert_eq!(res[0].cw721_address, suite.cw721);
+    assert_eq!(res[0].buyer, Addr::unchecked(BUYER));
+}
+
+fn trades(suite: &Suite, msg: &QueryMsg) -> Vec<Trade> {
+    let res: TradesResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(&suite.collection, msg)
+        .unwrap();
+    res.list
+}
+
+#[test]
+fn trade_history_and_stats() {
+    let mut suite = setup();
+    let fixed_id = start_fixed_sale(&mut suite, 100, JUNO);
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(BUYER),
+            suite.collection.clone(),
+            &ExecuteMsg::Propose {
+                cw721_address: None,
+                token_id: fixed_id.to_string(),
+                denom: JUNO.to_string(),
+                min_output: None,
+            },
+            &coins(100, JUNO),
+        )
+        .unwrap();
+
+    let auction_id = start_auction(&mut suite, DurationType::Fixed, 60);
+    bid(&mut suite, BUYER, auction_id, 60);
+    bid(&mut suite, OWNER, auction_id, 80);
+    suite
+        .app
+        .update_block(|block| block.time = block.time.plus_seconds(10));
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(SELLER),
+            suite.collection.clone(),
+            &ExecuteMsg::AcceptSale {
+                cw721_address: None,
+                token_id: auction_id.to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+
+    let now = suite.app.block_info().time.seconds();
+    let res = trades(
+        &suite,
+        &QueryMsg::TradesByToken {
+            cw721_address: None,
+            token_id: auction_id.to_string(),
+            start_after: None,
+            limit: None,
+        },
+    );
+    assert_eq!(
+        res,
+        vec![Trade {
+            id: 2,
+            cw721_address: suite.cw721.clone(),
+            token_id: auction_id.to_string(),
+            seller: Addr::unchecked(SELLER),
+            buyer: Addr::unchecked(OWNER),
+            price: Uint128::from(80u128),
+            denom: Denom::Native(JUNO.to_string()),
+            time: now,
+            sale_type: SaleType::Auction,
+        }]
+    );
+
+    // newest first, and start_after pages towards older trades
+    let res = trades(
+        &suite,
+        &QueryMsg::TradesByAccount {
+            address: Addr::unchecked(SELLER),
+            start_after: None,
+            limit: None,
+        },
+    );
+    assert_eq!(res.iter().map(|t| t.id).collect::<Vec<_>>(), vec![2, 1]);
+    let res = trades(
+        &suite,
+        &QueryMsg::TradesByAccount {
+            address: Addr::unchecked(SELLER),
+            start_after: Some(2),
+            limit: Some(1),
+        },
+    );
+    assert_eq!(res.len(), 1);
+    assert_eq!(res[0].token_id, fixed_id.to_string());
+    assert_eq!(res[0].buyer, Addr::unchecked(BUYER));
+    let res = trades(
+        &suite,
+        &QueryMsg::TradesByAccount {
+            address: Addr::unchecked(BUYER),
+            start_after: None,
+            limit: None,
+        },
+    );
+    assert_eq!(res.iter().map(|t| t.id).collect::<Vec<_>>(), vec![1]);
+
+    let stats: TradeStatsResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(
+            &suite.collection,
+            &QueryMsg::TradeStats {
+                cw721_address: None,
+                window: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(stats.last_trade.unwrap().id, 2);
+    assert_eq!(
+        stats.list,
+        vec![DenomTradeStats {
+            denom: Denom::Native(JUNO.to_string()),
+            trades: 2,
+            volume: Uint128::from(180u128),
+            last_price: Uint128::from(80u128),
+            floor_price: Uint128::from(80u128),
+        }]
+    );
+
+    let stats: TradeStatsResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(
+            &suite.collection,
+            &QueryMsg::TradeStats {
+                cw721_address: None,
+                window: Some(1),
+            },
+        )
+        .unwrap();
+    assert_eq!(stats.list[0].trades, 1);
+    assert_eq!(stats.list[0].volume, Uint128::from(80u128));
+}
+
+fn owner_execute(suite: &mut Suite, msg: &ExecuteMsg) -> Result<(), ContractError> {
+    suite
+        .app
+        .execute_contract(Addr::unchecked(OWNER), suite.collection.clone(), msg, &[])
+        .map(|_| ())
+        .map_err(|err| err.downcast().unwrap())
+}
+
+#[test]
+fn pause_scopes() {
+    let mut suite = setup();
+    let token_id = start_auction(&mut suite, DurationType::Fixed, 1_000);
+    mint(&mut suite, SELLER, None, &[]).unwrap();
+
+    let err = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(SELLER),
+            suite.collection.clone(),
+            &ExecuteMsg::SetPaused {
+                scope: PauseScope::List,
+                paused: true,
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::Unauthorized {}
+    ));
+
+    owner_execute(
+        &mut suite,
+        &ExecuteMsg::SetPaused {
+            scope: PauseScope::List,
+            paused: true,
+        },
+    )
+    .unwrap();
+    let res: PausedResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(&suite.collection, &QueryMsg::Paused {})
+        .unwrap();
+    assert_eq!(
+        res,
+        PausedResponse {
+            enabled: true,
+            list: vec![PauseScope::List],
+        }
+    );
+
+    let err = list_nft(
+        &mut suite,
+        token_id + 1,
+        &NftReceiveMsg::StartSale {
+            sale_type: SaleType::Fixed,
+            duration_type: DurationType::Fixed,
+            initial_price: Uint128::from(100u128),
+            reserve_price: Uint128::from(100u128),
+            denom: Denom::Native(JUNO.to_string()),
+        },
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Paused {}));
+
+    // the other scopes keep running
+    mint(&mut suite, SELLER, None, &[]).unwrap();
+    bid(&mut suite, BUYER, token_id, 100);
+
+    owner_execute(
+        &mut suite,
+        &ExecuteMsg::SetPaused {
+            scope: PauseScope::Bid,
+            paused: true,
+        },
+    )
+    .unwrap();
+    let err = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(BUYER),
+            suite.collection.clone(),
+            &ExecuteMsg::Propose {
+                cw721_address: None,
+                token_id: token_id.to_string(),
+                denom: JUNO.to_string(),
+                min_output: None,
+            },
+            &coins(200, JUNO),
+        )
+        .unwrap_err();
+    assert!(matches!(err.downcast().unwrap(), ContractError::Paused {}));
+
+    owner_execute(
+        &mut suite,
+        &ExecuteMsg::SetPaused {
+            scope: PauseScope::List,
+            paused: false,
+        },
+    )
+    .unwrap();
+    let res: PausedResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(&suite.collection, &QueryMsg::Paused {})
+        .unwrap();
+    assert_eq!(res.list, vec![PauseScope::Bid]);
+}
+
+#[test]
+fn emergency_unwinding() {
+    let mut suite = setup();
+    let accepted = start_auction(&mut suite, DurationType::Fixed, 150);
+    bid(&mut suite, BUYER, accepted, 100);
+    bid(&mut suite, OWNER, accepted, 200);
+    let other = start_auction(&mut suite, DurationType::Fixed, 1_000);
+    bid(&mut suite, BUYER, other, 100);
+    let last = start_auction(&mut suite, DurationType::Fixed, 1_000);
+
+    let err = owner_execute(&mut suite, &ExecuteMsg::UnwindAllSales { limit: None }).unwrap_err();
+    assert!(matches!(err, ContractError::NotDisabled {}));
+
+    owner_execute(&mut suite, &ExecuteMsg::UpdateEnabled { enabled: false }).unwrap();
+    let err = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(SELLER),
+            suite.collection.clone(),
+            &ExecuteMsg::AcceptSale {
+                cw721_address: None,
+                token_id: accepted.to_string(),
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::Disabled {}
+    ));
+
+    // the leading bid and the accepted sale can both be left
+    cancel_bid(&mut suite, OWNER, accepted, 2).unwrap();
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(SELLER),
+            suite.collection.clone(),
+            &ExecuteMsg::CancelSale {
+                cw721_address: None,
+                token_id: accepted.to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(owner_of(&suite, accepted), SELLER);
+    assert_eq!(balance(&suite, BUYER, JUNO), 900);
+
+    let err = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(SELLER),
+            suite.collection.clone(),
+            &ExecuteMsg::UnwindAllSales { limit: None },
+            &[],
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::Unauthorized {}
+    ));
+
+    owner_execute(&mut suite, &ExecuteMsg::UnwindAllSales { limit: Some(1) }).unwrap();
+    assert_eq!(owner_of(&suite, other), SELLER);
+    assert_eq!(owner_of(&suite, last), suite.collection.to_string());
+    assert_eq!(balance(&suite, BUYER, JUNO), 1_000);
+
+    owner_execute(&mut suite, &ExecuteMsg::UnwindAllSales { limit: None }).unwrap();
+    assert_eq!(owner_of(&suite, last), SELLER);
+    assert_eq!(escrow(&suite)[0].amount, Uint128::zero());
+}
+
+fn has_role(suite: &Suite, role: Role, address: &str) -> bool {
+    let res: HasRoleResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(
+            &suite.collection,
+            &QueryMsg::HasRole {
+                role,
+                address: Addr::unchecked(address),
+            },
+        )
+        .unwrap();
+    res.has_role
+}
+
+#[test]
+fn role_based_access() {
+    let mut suite = setup();
+    let minter = "minter";
+    let moderator = "moderator";
+    assert!(has_role(&suite, Role::Minter, OWNER));
+    assert!(!has_role(&suite, Role::Minter, minter));
+
+    let err = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(minter),
+            suite.collection.clone(),
+            &ExecuteMsg::GrantRole {
+                role: Role::Minter,
+                address: Addr::unchecked(minter),
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::Unauthorized {}
+    ));
+
+    owner_execute(
+        &mut suite,
+        &ExecuteMsg::GrantRole {
+            role: Role::Minter,
+            address: Addr::unchecked(minter),
+        },
+    )
+    .unwrap();
+    assert!(has_role(&suite, Role::Minter, minter));
+    assert!(!has_role(&suite, Role::Pauser, minter));
+
+    let batch = ExecuteMsg::BatchMint {
+        uri: vec!["ipfs://token".to_string()],
+        extension: vec![None],
+        owner: vec![SELLER.to_string()],
+    };
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(minter),
+            suite.collection.clone(),
+            &batch,
+            &[],
+        )
+        .unwrap();
+
+    // a role only covers its own actions
+    let err = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(minter),
+            suite.collection.clone(),
+            &ExecuteMsg::UpdateEnabled { enabled: false },
+            &[],
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::Unauthorized {}
+    ));
+
+    owner_execute(
+        &mut suite,
+        &ExecuteMsg::RevokeRole {
+            role: Role::Minter,
+            address: Addr::unchecked(minter),
+        },
+    )
+    .unwrap();
+    let err = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(minter),
+            suite.collection.clone(),
+            &batch,
+            &[],
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::Unauthorized {}
+    ));
+
+    // moderators can delist someone else's sale
+    let token_id = start_fixed_sale(&mut suite, 100, JUNO);
+    let cancel = ExecuteMsg::CancelSale {
+        cw721_address: None,
+        token_id: token_id.to_string(),
+    };
+    let err = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(moderator),
+            suite.collection.clone(),
+            &cancel,
+            &[],
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::Unauthorized {}
+    ));
+    owner_execute(
+        &mut suite,
+        &ExecuteMsg::GrantRole {
+            role: Role::SaleModerator,
+            address: Addr::unchecked(moderator),
+        },
+    )
+    .unwrap();
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(moderator),
+            suite.collection.clone(),
+            &cancel,
+            &[],
+        )
+        .unwrap();
+    assert_eq!(owner_of(&suite, token_id), SELLER);
+
+    // handing over the cw721 contract stays with the owner
+    for msg in [
+        ExecuteMsg::ChangeContract {
+            cw721_address: Addr::unchecked("other_cw721"),
+        },
+        ExecuteMsg::ChangeCw721Owner {
+            owner: Addr::unchecked(minter),
+        },
+    ] {
+        let err = suite
+            .app
+            .execute_contract(Addr::unchecked(minter), suite.collection.clone(), &msg, &[])
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast().unwrap(),
+            ContractError::Unauthorized {}
+        ));
+    }
+}
+
+#[test]
+fn change_contract_waits_for_open_sales() {
+    let mut suite = setup();
+    let token_id = start_fixed_sale(&mut suite, 100, JUNO);
+    assert_eq!(
+        sale(&suite, token_id).cw721_address,
+        Some(suite.cw721.clone())
+    );
+
+    let change = ExecuteMsg::ChangeContract {
+        cw721_address: Addr::unchecked("other_cw721"),
+    };
+    let err = owner_execute(&mut suite, &change).unwrap_err();
+    assert!(matches!(err, ContractError::SalesOpen {}));
+
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(SELLER),
+            suite.collection.clone(),
+            &ExecuteMsg::CancelSale {
+                cw721_address: None,
+                token_id: token_id.to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+    owner_execute(&mut suite, &change).unwrap();
+}
+
+#[test]
+fn external_cw721_sales() {
+    let mut suite = setup();
+    let cw721_id = suite.app.store_code(contract_cw721());
+    let external = suite
+        .app
+        .instantiate_contract(
+            cw721_id,
+            Addr::unchecked(OWNER),
+            &Cw721InstantiateMsg {
+                name: "External".to_string(),
+                symbol: "EXT".to_string(),
+                minter: OWNER.to_string(),
+                soulbound: false,
+                index_traits: false,
+            },
+            &[],
+            "external",
+            None,
+        )
+        .unwrap();
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            external.clone(),
+            &Cw721ExecuteMsg::Mint(MintMsg::<Extension> {
+                token_id: "1".to_string(),
+                owner: SELLER.to_string(),
+                token_uri: None,
+                extension: None,
+                soulbound: false,
+            }),
+            &[],
+        )
+        .unwrap();
+    let start_sale = |suite: &mut Suite| {
+        suite.app.execute_contract(
+            Addr::unchecked(SELLER),
+            external.clone(),
+            &Cw721ExecuteMsg::<Extension>::SendNft {
+                contract: suite.collection.to_string(),
+                token_id: "1".to_string(),
+                msg: to_binary(&NftReceiveMsg::StartSale {
+                    sale_type: SaleType::Fixed,
+                    duration_type: DurationType::Fixed,
+                    initial_price: Uint128::from(100u128),
+                    reserve_price: Uint128::from(100u128),
+                    denom: Denom::Native(JUNO.to_string()),
+                })
+                .unwrap(),
+            },
+            &[],
+        )
+    };
+
+    let err = start_sale(&mut suite).unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::InvalidCw721Token {}
+    ));
+
+    let whitelist = |rate: u32| ExecuteMsg::WhitelistCw721 {
+        cw721_address: external.clone(),
+        royalty: RoyaltySource::Registry {
+            royalties: vec![Royalty {
+                address: Addr::unchecked("artist"),
+                rate,
+            }],
+        },
+    };
+    let err = owner_execute(&mut suite, &whitelist(150_000)).unwrap_err();
+    assert!(matches!(err, ContractError::ExceedsMaximumRoyaltyFee {}));
+    owner_execute(&mut suite, &whitelist(100_000)).unwrap();
+    let res: Cw721WhitelistResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(
+            &suite.collection,
+            &QueryMsg::Cw721Whitelist {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(res.list.len(), 1);
+
+    // the same token id is listed independently on the collection's own contract
+    let own_id = start_fixed_sale(&mut suite, 100, JUNO);
+    assert_eq!(own_id, 1);
+    start_sale(&mut suite).unwrap();
+    let listed: SaleInfo = suite
+        .app
+        .wrap()
+        .query_wasm_smart(
+            &suite.collection,
+            &QueryMsg::GetSale {
+                cw721_address: Some(external.clone()),
+                token_id: 1.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(listed.cw721_address, Some(external.clone()));
+    assert_eq!(
+        sale(&suite, own_id).cw721_address,
+        Some(suite.cw721.clone())
+    );
+
+    let remove = ExecuteMsg::RemoveCw721 {
+        cw721_address: external.clone(),
+    };
+    let err = owner_execute(&mut suite, &remove).unwrap_err();
+    assert!(matches!(err, ContractError::SalesOpen {}));
+
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(BUYER),
+            suite.collection.clone(),
+            &ExecuteMsg::Propose {
+                cw721_address: Some(external.clone()),
+                token_id: 1.to_string(),
+                denom: JUNO.to_string(),
+                min_output: None,
+            },
+            &coins(100, JUNO),
+        )
+        .unwrap();
+    let owner: OwnerOfResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(
+            &external,
+            &Cw721QueryMsg::OwnerOf {
+                token_id: "1".to_string(),
+                include_expired: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(owner.owner, BUYER);
+    assert_eq!(owner_of(&suite, own_id), suite.collection.to_string());
+    // registry royalties of the external contract, not the collection's own ones
+    assert_eq!(balance(&suite, "artist", JUNO), 10);
+    assert_eq!(balance(&suite, SELLER, JUNO), 90);
+
+    let res = trades(
+        &suite,
+        &QueryMsg::TradesByToken {
+            cw721_address: Some(external.clone()),
+            token_id: 1.to_string(),
+            start_after: None,
+            limit: None,
+        },
+    );
+    assert_eq!(res.len(), 1);
+    assert_eq!(res[0].cw721_address, external);
+    let stats: TradeStatsResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(
+            &suite.collection,
+            &QueryMsg::TradeStats {
+                cw721_address: Some(suite.cw721.clone()),
+                window: None,
+            },
+        )
+        .unwrap();
+    assert!(stats.last_trade.is_none());
+
+    owner_execute(&mut suite, &remove).unwrap();
+}
+
+#[test]
+fn numeric_token_ids_still_accepted() {
+    let msg: ExecuteMsg = from_slice(br#"{"sweep_buy":{"token_ids":[1,"two"]}}"#).unwrap();
+    assert_eq!(
+        msg,
+        ExecuteMsg::SweepBuy {
+            cw721_address: None,
+            token_ids: vec!["1".to_string(), "two".to_string()],
+        }
+    );
+    let msg: QueryMsg = from_slice(br#"{"get_sales":{"start_after":3}}"#).unwrap();
+    assert_eq!(
+        msg,
+        QueryMsg::GetSales {
+            cw721_address: None,
+            start_after: Some("3".to_string()),
+            limit: None,
+        }
+    );
+    let msg: QueryMsg = from_slice(br#"{"get_sales":{}}"#).unwrap();
+    assert!(matches!(
+        msg,
+        QueryMsg::GetSales {
+            start_after: None,
+            ..
+        }
+    ));
+
+    let mut suite = setup();
+    let token_id = start_fixed_sale(&mut suite, 100, JUNO);
+    let propose = format!(
+        r#"{{"propose":{{"token_id":{},"denom":"{}","min_output":null}}}}"#,
+        token_id, JUNO
+    );
+    suite
+        .app
+        .execute(
+            Addr::unchecked(BUYER),
+            WasmMsg::Execute {
+                contract_addr: suite.collection.to_string(),
+                msg: Binary::from(propose.as_bytes()),
+                funds: coins(100, JUNO),
+            }
+            .into(),
+        )
+        .unwrap();
+    assert_eq!(owner_of(&suite, token_id), BUYER);
+    assert_eq!(
+        trades(
+            &suite,
+            &QueryMsg::TradesByAccount {
+                address: Addr::unchecked(BUYER),
+                start_after: None,
+                limit: None,
+            },
+        )[0]
+        .token_id,
+        token_id.to_string()
+    );
+}
+
+#[test]
+fn transfer_policy_keeps_trades_on_the_marketplace() {
+    let mut suite = setup();
+    mint(&mut suite, SELLER, None, &[]).unwrap();
+    let gift_id = 1;
+
+    let err = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(SELLER),
+            suite.collection.clone(),
+            &ExecuteMsg::SetTransferPolicy { policy: None },
+            &[],
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::Unauthorized {}
+    ));
+
+    let collection = suite.collection.clone();
+    owner_execute(
+        &mut suite,
+        &ExecuteMsg::SetTransferPolicy {
+            policy: Some(TransferPolicy {
+                rule: TransferRule::Operators {
+                    operators: vec![collection],
+                },
+                allow_gifts: false,
+            }),
+        },
+    )
+    .unwrap();
+    let res: TransferPolicyResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(&suite.cw721, &Cw721QueryMsg::TransferPolicy {})
+        .unwrap();
+    assert!(res.policy.is_some());
+
+    // direct transfers bypass royalties and are refused
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(SELLER),
+            suite.cw721.clone(),
+            &Cw721ExecuteMsg::<Extension>::TransferNft {
+                recipient: BUYER.to_string(),
+                token_id: gift_id.to_string(),
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(owner_of(&suite, gift_id), SELLER);
+
+    // listing on and buying through the collection still work
+    let token_id = start_fixed_sale(&mut suite, 100, ATOM);
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(BUYER),
+            suite.collection.clone(),
+            &ExecuteMsg::Propose {
+                cw721_address: None,
+                token_id: token_id.to_string(),
+                denom: ATOM.to_string(),
+                min_output: None,
+            },
+            &coins(100, ATOM),
+        )
+        .unwrap();
+    assert_eq!(owner_of(&suite, token_id), BUYER);
+
+    owner_execute(&mut suite, &ExecuteMsg::SetTransferPolicy { policy: None }).unwrap();
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(SELLER),
+            suite.cw721.clone(),
+            &Cw721ExecuteMsg::<Extension>::TransferNft {
+                recipient: BUYER.to_string(),
+                token_id: gift_id.to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(owner_of(&suite, gift_id), BUYER);
+}
+
+#[test]
+fn soulbound_mint_phase() {
+    let mut suite = setup();
+    let now = suite.app.block_info().time.seconds();
+    mint(&mut suite, SELLER, None, &[]).unwrap();
+
+    update_mint_phase(
+        &mut suite,
+        MintPhase {
+            start: now,
+            end: now + 86_400,
+            price: Uint128::zero(),
+            denom: Denom::Native(JUNO.to_string()),
+            per_wallet_limit: None,
+            merkle_root: None,
+            soulbound: true,
+        },
+    );
+    mint(&mut suite, SELLER, None, &[]).unwrap();
+
+    let soulbound = |suite: &Suite, token_id: u32| {
+        let res: cw721_base::msg::NftInfoResponse<Extension> = suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                &suite.cw721,
+                &Cw721QueryMsg::NftInfo {
+                    token_id: token_id.to_string(),
+                },
+            )
+            .unwrap();
+        res.soulbound
+    };
+    assert!(!soulbound(&suite, 1));
+    assert!(soulbound(&suite, 2));
+
+    // the badge can't be listed, the earlier token still can
+    let err = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(SELLER),
+            suite.cw721.clone(),
+            &Cw721ExecuteMsg::<Extension>::SendNft {
+                contract: suite.collection.to_string(),
+                token_id: "2".to_string(),
+                msg: to_binary(&NftReceiveMsg::StartSale {
+                    sale_type: SaleType::Fixed,
+                    duration_type: DurationType::Fixed,
+                    initial_price: Uint128::from(100u128),
+                    reserve_price: Uint128::from(100u128),
+                    denom: Denom::Native(ATOM.to_string()),
+                })
+                .unwrap(),
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<cw721_base::ContractError>().unwrap(),
+        cw721_base::ContractError::Soulbound {}
+    );
+    assert_eq!(owner_of(&suite, 2), SELLER);
+    list_nft(
+        &mut suite,
+        1,
+        &NftReceiveMsg::StartSale {
+            sale_type: SaleType::Fixed,
+            duration_type: DurationType::Fixed,
+            initial_price: Uint128::from(100u128),
+            reserve_price: Uint128::from(100u128),
+            denom: Denom::Native(ATOM.to_string()),
+        },
+    )
+    .unwrap();
+}
@@ -1,5 +1,6 @@
 use cosmwasm_std::{StdError, Uint128};
 use thiserror::Error;
+use marble_roles::RoleError;
 use cw_utils::{Expiration, Scheduled};
 
 #[derive(Error, Debug)]
@@ -155,4 +156,79 @@ pub enum ContractError {
 
     #[error("InsufficientRoyalty")]
     InsufficientRoyalty {},
+
+    #[error("UnsupportedDenom")]
+    UnsupportedDenom {},
+
+    #[error("DenomAlreadySupported")]
+    DenomAlreadySupported {},
+
+    #[error("NoActiveMintPhase")]
+    NoActiveMintPhase {},
+
+    #[error("MintPhaseOverlap")]
+    MintPhaseOverlap {},
+
+    #[error("MintPhaseNotFound")]
+    MintPhaseNotFound {},
+
+    #[error("WalletLimitExceeded")]
+    WalletLimitExceeded {},
+
+    #[error("InvalidBatchSize")]
+    InvalidBatchSize {},
+
+    #[error("InvalidUnusedTokenId")]
+    InvalidUnusedTokenId {},
+
+    #[error("AlreadyRevealed")]
+    AlreadyRevealed {},
+
+    #[error("NotRevealed")]
+    NotRevealed {},
+
+    #[error("RevealPending")]
+    RevealPending {},
+
+    #[error("ShufflePending")]
+    ShufflePending {},
+
+    #[error("MintOngoing")]
+    MintOngoing {},
+
+    #[error("NoAirdrop")]
+    NoAirdrop {},
+
+    #[error("NoSwapRoute")]
+    NoSwapRoute {},
+
+    #[error("SwapOutputTooLow: {output} < {min_output}")]
+    SwapOutputTooLow { output: Uint128, min_output: Uint128 },
+
+    #[error("BidNotFound")]
+    BidNotFound {},
+
+    #[error("LeadingBidLocked")]
+    LeadingBidLocked {},
+
+    #[error("Paused")]
+    Paused {},
+
+    #[error("NotDisabled")]
+    NotDisabled {},
+
+    #[error("SalesOpen")]
+    SalesOpen {},
+
+    #[error("InvalidTokenId")]
+    InvalidTokenId {},
+}
+
+impl From<RoleError> for ContractError {
+    fn from(err: RoleError) -> Self {
+        match err {
+            RoleError::Std(err) => ContractError::Std(err),
+            RoleError::Unauthorized {} => ContractError::Unauthorized {}
+        }
+    }
 }
@@ -2,11 +2,14 @@ use cosmwasm_std::{Addr, Uint128};
 use cw20::Cw20ReceiveMsg;
 use cw721::Cw721ReceiveMsg;
 
-use cw721_base::Extension;
+use cw721_base::{Extension, TransferPolicy};
 use schemars::JsonSchema;
-use serde::{Deserialize, Serialize};
-use cw_utils::{Expiration, Scheduled};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde::de::{self, Visitor};
+use std::fmt;
+use cw_utils::Expiration;
 use cw20::Denom;
+pub use marble_roles::{HasRoleResponse, Role};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
@@ -17,7 +20,13 @@ pub struct InstantiateMsg {
     pub token_code_id: u64,
     pub maximum_royalty_fee: u32,
     pub royalties: Vec<Royalty>,
-    pub uri: String
+    pub uri: String,
+    /// Hex encoded sha256 of a secret seed of the owner. When set, placeholder tokens reveal
+    /// shuffled metadata ids, drawn from the seed once it is revealed after mint-out
+    pub shuffle_commit: Option<String>,
+    /// Has the cw721 contract index token attributes for its TokensByTrait and TraitCounts queries
+    #[serde(default)]
+    pub index_traits: bool
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -26,51 +35,196 @@ pub enum ExecuteMsg {
     UpdateOwner {
         owner: Addr,
     },
+    /// Disabling halts every pause scope, while CancelSale, CancelPropose and UnwindAllSales stay open
+    /// and no longer wait on accepted or leading bids
     UpdateEnabled {
         enabled: bool
     },
+    /// Lets address act in the given role, only the owner can grant and revoke roles
+    GrantRole {
+        role: Role,
+        address: Addr
+    },
+    RevokeRole {
+        role: Role,
+        address: Addr
+    },
+    /// Halts or resumes one part of the contract on its own
+    SetPaused {
+        scope: PauseScope,
+        paused: bool
+    },
+    /// Returns the NFTs and bids of up to limit sales while the contract is disabled
+    UnwindAllSales {
+        limit: Option<u32>
+    },
+    /// Sums the bids of up to limit more sales into the escrow ledger, starting over from
+    /// an empty ledger when no rebuild is running. Repeat until the response says it is complete
+    RebuildEscrow {
+        limit: Option<u32>
+    },
     UpdateRoyalties {
         maximum_royalty_fee: u32,
         royalties: Vec<Royalty>
     },
-    Mint {uri: String, extension: Extension},
-    // Edit {token_id: u32, uri: String, extension: Extension},
+    Mint {uri: String, extension: Extension, proof: Option<Vec<String>>},
+    EditToken {token_id: u32, uri: String, extension: Extension},
+    /// Freezes or unfreezes the metadata of one token, or of the whole collection if token_id is unset
+    SetTokenFrozen {token_id: Option<u32>, frozen: bool},
+    /// Permanently freezes the metadata of every token and stops any further minting.
+    /// Hashes up to `limit` tokens per call, repeat until the provenance hash is set
+    FreezeMetadata {limit: Option<u32>},
     BatchMint {
         uri: Vec<String>, 
         extension:Vec<Extension>,
         owner: Vec<String>
     },
     Propose {
-        token_id: u32,
-        denom: String
+        /// Defaults to the collection's own cw721 contract, as everywhere a sale is targeted
+        cw721_address: Option<Addr>,
+        #[serde(deserialize_with = "token_id")]
+        token_id: String,
+        denom: String,
+        min_output: Option<Uint128>
     },
     Receive(Cw20ReceiveMsg),
+    /// Lists a token sent from the collection's own cw721 contract or a whitelisted one
     ReceiveNft(Cw721ReceiveMsg),
     AcceptSale {
-        token_id: u32
+        cw721_address: Option<Addr>,
+        #[serde(deserialize_with = "token_id")]
+        token_id: String
     },
     CancelSale {
-        token_id: u32,
+        cw721_address: Option<Addr>,
+        #[serde(deserialize_with = "token_id")]
+        token_id: String,
     },
+    /// Points the collection at another cw721 contract. Owner only, like UpdateOwner,
+    /// as swapping the contract hands control of the collection to whoever runs it
     ChangeContract {
         cw721_address: Addr
     },
+    /// Makes owner the minter of the cw721 contract. Owner only, as the minter can
+    /// mint and edit freely outside this contract's mint phases and royalties
     ChangeCw721Owner {
         owner: Addr
     },
+    /// Sets the transfer policy of the cw721 contract, which this contract mints for.
+    /// Allowlisting this contract as an operator keeps trades going through its royalties
+    SetTransferPolicy {
+        policy: Option<TransferPolicy>
+    },
     UpdateUnusedTokenId {
         token_id: u32
     },
     EditSale {
-        token_id: u32,
+        cw721_address: Option<Addr>,
+        #[serde(deserialize_with = "token_id")]
+        token_id: String,
         sale_type: SaleType,
         duration_type: DurationType,
         initial_price: Uint128,
         reserve_price: Uint128,
         denom: Denom
     },
+    /// Pulls approved tokens of the sender into the contract and lists them
+    BatchStartSale {
+        items: Vec<SaleItem>
+    },
+    BatchCancelSale {
+        cw721_address: Option<Addr>,
+        #[serde(deserialize_with = "token_ids")]
+        token_ids: Vec<String>
+    },
+    BatchEditSale {
+        items: Vec<SaleItem>
+    },
+    /// Buys several fixed price listings at once, the native funds must cover their exact total
+    SweepBuy {
+        cw721_address: Option<Addr>,
+        #[serde(deserialize_with = "token_ids")]
+        token_ids: Vec<String>
+    },
+    /// Buys fixed price listings in order until max_total is spent, skipping the unavailable ones.
+    /// Unspent funds are refunded and the response data holds a SweepResponse
+    Sweep {
+        cw721_address: Option<Addr>,
+        #[serde(deserialize_with = "token_ids")]
+        token_ids: Vec<String>,
+        max_total: Uint128
+    },
+    /// Withdraws one of the sender's bids. The leading bid stays locked while a timed auction runs,
+    /// and afterwards as long as it meets the reserve price, so the seller can still accept it
     CancelPropose {
-        token_id: u32
+        cw721_address: Option<Addr>,
+        #[serde(deserialize_with = "token_id")]
+        token_id: String,
+        bid_id: u32
+    },
+    AddSupportedDenom {
+        denom: Denom,
+        pool_address: Option<Addr>,
+        decimals: u8,
+        symbol: String
+    },
+    UpdateSupportedDenom {
+        denom: Denom,
+        pool_address: Option<Addr>,
+        decimals: u8,
+        symbol: String,
+        enabled: bool
+    },
+    RemoveSupportedDenom {
+        denom: Denom
+    },
+    /// Lets tokens of another cw721 contract be listed, or changes where its royalties come from
+    WhitelistCw721 {
+        cw721_address: Addr,
+        royalty: RoyaltySource
+    },
+    /// Refused while tokens of the contract are listed
+    RemoveCw721 {
+        cw721_address: Addr
+    },
+    AddMintPhase {
+        phase: MintPhase
+    },
+    UpdateMintPhase {
+        phase_id: u32,
+        phase: MintPhase
+    },
+    RemoveMintPhase {
+        phase_id: u32
+    },
+    UpdatePayoutAddress {
+        payout_address: Addr
+    },
+    RegisterAirdrop {
+        airdrop: Airdrop
+    },
+    SetPlaceholderUri {
+        placeholder_uri: String
+    },
+    /// Waits for RevealShuffleSeed when the collection shuffles its metadata
+    Reveal {
+        base_uri: String,
+        provenance_hash: String
+    },
+    /// Reveals the seed committed at instantiate, once every token is minted. Mixed with the
+    /// current block, it decides which metadata id each placeholder token reveals
+    RevealShuffleSeed {
+        seed: String
+    },
+    /// Points the next batch of placeholder tokens to their final uri, callable by anyone after reveal
+    RevealTokens {
+        limit: Option<u32>
+    },
+    ClaimAirdrop {
+        /// Position of the (address, amount) entry in the airdrop list, used for the claimed bitmap
+        index: u32,
+        amount: u32,
+        proof: Vec<String>
     }
 }
 
@@ -78,7 +232,21 @@ pub enum ExecuteMsg {
 #[serde(rename_all = "snake_case")]
 pub enum ReceiveMsg {
     Propose {
-        token_id: u32
+        cw721_address: Option<Addr>,
+        #[serde(deserialize_with = "token_id")]
+        token_id: String,
+        min_output: Option<Uint128>
+    },
+    Mint {
+        uri: String,
+        extension: Extension,
+        proof: Option<Vec<String>>
+    },
+    Sweep {
+        cw721_address: Option<Addr>,
+        #[serde(deserialize_with = "token_ids")]
+        token_ids: Vec<String>,
+        max_total: Uint128
     }
 }
 
@@ -94,18 +262,113 @@ pub enum NftReceiveMsg {
     }
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SaleItem {
+    /// Defaults to the collection's own cw721 contract
+    #[serde(default)]
+    pub cw721_address: Option<Addr>,
+    #[serde(deserialize_with = "token_id")]
+    pub token_id: String,
+    pub sale_type: SaleType,
+    pub duration_type: DurationType,
+    pub initial_price: Uint128,
+    pub reserve_price: Uint128,
+    pub denom: Denom
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SweepResult {
+    Bought { price: Uint128 },
+    NotOnSale,
+    NotFixedSale,
+    DenomMismatch,
+    OverBudget
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SweepItem {
+    pub token_id: String,
+    pub result: SweepResult
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SweepResponse {
+    pub spent: Uint128,
+    pub refund: Uint128,
+    pub list: Vec<SweepItem>
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     GetConfig {},
     GetSale {
-        token_id: u32,
+        cw721_address: Option<Addr>,
+        #[serde(deserialize_with = "token_id")]
+        token_id: String,
     },
+    /// Sales of one cw721 contract, the collection's own one by default
     GetSales {
-        start_after: Option<u32>,
+        cw721_address: Option<Addr>,
+        #[serde(default, deserialize_with = "optional_token_id")]
+        start_after: Option<String>,
+        limit: Option<u32>
+    },
+    GetSupportedDenom {
+        denom: Denom
+    },
+    GetSupportedDenoms {
+        start_after: Option<String>,
+        limit: Option<u32>
+    },
+    MintPhases {},
+    MintedBy {
+        address: Addr
+    },
+    Airdrop {},
+    IsClaimed {
+        index: u32
+    },
+    RevealInfo {},
+    MetadataFrozen {},
+    Provenance {},
+    Supply {},
+    /// Bid funds held for running auctions, per denom
+    EscrowBalances {},
+    /// Compares the escrow ledger with the contract balances. Public, as it only reads
+    /// what is already on chain
+    VerifyEscrow {},
+    /// Completed sales of a token, newest first
+    TradesByToken {
+        cw721_address: Option<Addr>,
+        #[serde(deserialize_with = "token_id")]
+        token_id: String,
+        start_after: Option<u64>,
         limit: Option<u32>
     },
-    
+    /// Completed sales with the address as seller or buyer, newest first
+    TradesByAccount {
+        address: Addr,
+        start_after: Option<u64>,
+        limit: Option<u32>
+    },
+    /// Per-denom price statistics over the last `window` trades, of every cw721 contract
+    /// unless one is given
+    TradeStats {
+        cw721_address: Option<Addr>,
+        window: Option<u32>
+    },
+    Cw721Whitelist {
+        start_after: Option<Addr>,
+        limit: Option<u32>
+    },
+    Paused {},
+    /// The owner holds every role
+    HasRole {
+        role: Role,
+        address: Addr
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -119,12 +382,16 @@ pub struct ConfigResponse {
     pub maximum_royalty_fee: u32,
     pub royalties: Vec<Royalty>,
     pub uri: String,
-    pub enabled: bool
+    pub enabled: bool,
+    pub shuffle: bool
 }
 
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Request {
+    /// Stays the same while other bids on the sale are withdrawn
+    #[serde(default)]
+    pub id: u32,
     pub address: Addr,
     pub price: Uint128
 }
@@ -157,7 +424,8 @@ pub struct Royalty {
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct SaleInfo {
-    pub token_id: u32,
+    #[serde(deserialize_with = "token_id")]
+    pub token_id: String,
     pub provider: Addr,
     pub sale_type: SaleType,
     pub duration_type: DurationType,
@@ -165,7 +433,27 @@ pub struct SaleInfo {
     pub reserve_price: Uint128,
     pub requests: Vec<Request>,
     pub denom: Denom,
-    pub can_accept: bool 
+    pub can_accept: bool,
+    /// cw721 contract holding the token, unset for sales listed before it was recorded
+    #[serde(default)]
+    pub cw721_address: Option<Addr>,
+    /// Number of bids ever placed, the last one has this id
+    #[serde(default)]
+    pub bid_count: u32,
+    /// Terms the sale had before each of its last 10 EditSale calls, oldest first
+    #[serde(default)]
+    pub edits: Vec<SaleEdit>
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SaleEdit {
+    /// Block time of the edit in seconds
+    pub time: u64,
+    pub sale_type: SaleType,
+    pub duration_type: DurationType,
+    pub initial_price: Uint128,
+    pub reserve_price: Uint128,
+    pub denom: Denom
 }
 
 
@@ -177,4 +465,271 @@ pub struct SalesResponse {
 
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct MigrateMsg {}
\ No newline at end of file
+#[serde(rename_all = "snake_case")]
+pub enum RoyaltySource {
+    /// The seller gets the whole price
+    None,
+    /// Rates kept here, scaled like the collection royalties
+    Registry {
+        royalties: Vec<Royalty>
+    },
+    /// Asked from the cw721 contract with the cw2981 royalty_info query
+    Cw2981
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WhitelistedCw721 {
+    pub cw721_address: Addr,
+    pub royalty: RoyaltySource
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Cw721WhitelistResponse {
+    pub list: Vec<WhitelistedCw721>
+}
+
+/// Query of cw2981 compliant cw721 contracts, sent as their extension query
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw2981QueryMsg {
+    Extension {
+        msg: Cw2981Query
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw2981Query {
+    RoyaltyInfo {
+        token_id: String,
+        sale_price: Uint128
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RoyaltiesInfoResponse {
+    pub address: String,
+    pub royalty_amount: Uint128
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SupportedDenom {
+    pub denom: Denom,
+    /// Wasmswap pool used to convert payments in this denom, if any
+    pub pool_address: Option<Addr>,
+    pub decimals: u8,
+    pub symbol: String,
+    pub enabled: bool
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PauseScope {
+    /// Mint, BatchMint and ClaimAirdrop
+    Mint,
+    /// Starting and editing sales
+    List,
+    /// Proposals, fixed price buys and sweeps
+    Bid,
+    /// AcceptSale
+    Settle
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PausedResponse {
+    pub enabled: bool,
+    pub list: Vec<PauseScope>
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Trade {
+    pub id: u64,
+    pub cw721_address: Addr,
+    pub token_id: String,
+    pub seller: Addr,
+    pub buyer: Addr,
+    pub price: Uint128,
+    pub denom: Denom,
+    /// Block time of the sale in seconds
+    pub time: u64,
+    pub sale_type: SaleType
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TradesResponse {
+    pub list: Vec<Trade>
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DenomTradeStats {
+    pub denom: Denom,
+    pub trades: u32,
+    pub volume: Uint128,
+    pub last_price: Uint128,
+    /// Lowest price paid within the window
+    pub floor_price: Uint128
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TradeStatsResponse {
+    pub last_trade: Option<Trade>,
+    pub list: Vec<DenomTradeStats>
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EscrowBalance {
+    pub denom: Denom,
+    pub amount: Uint128
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EscrowBalancesResponse {
+    pub list: Vec<EscrowBalance>
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EscrowCheck {
+    pub denom: Denom,
+    pub escrow: Uint128,
+    pub balance: Uint128
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VerifyEscrowResponse {
+    /// Whether the contract holds at least the escrowed amount of every denom
+    pub solvent: bool,
+    /// Whether a RebuildEscrow run is still in progress, leaving the ledger incomplete
+    pub rebuilding: bool,
+    pub list: Vec<EscrowCheck>
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SupportedDenomsResponse {
+    pub list: Vec<SupportedDenom>
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MintPhase {
+    pub start: u64,
+    pub end: u64,
+    pub price: Uint128,
+    pub denom: Denom,
+    /// Maximum tokens one wallet can mint during this phase, unlimited if unset
+    pub per_wallet_limit: Option<u32>,
+    /// Hex encoded sha256 Merkle root of the allowlisted addresses, public phase if unset
+    pub merkle_root: Option<String>,
+    /// Tokens minted during this phase can not be transferred, e.g. membership badges
+    #[serde(default)]
+    pub soulbound: bool
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MintPhaseInfo {
+    pub phase_id: u32,
+    pub phase: MintPhase
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MintPhasesResponse {
+    pub payout_address: Addr,
+    pub list: Vec<MintPhaseInfo>
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PhaseMinted {
+    pub phase_id: u32,
+    pub count: u32
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MintedByResponse {
+    pub address: Addr,
+    pub total: u32,
+    pub list: Vec<PhaseMinted>
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Airdrop {
+    /// Hex encoded sha256 Merkle root of the "index:address:amount" entries
+    pub merkle_root: String,
+    pub expiration: Expiration,
+    pub token_uri: String,
+    pub extension: Extension
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AirdropResponse {
+    pub airdrop_id: u32,
+    pub airdrop: Option<Airdrop>
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct IsClaimedResponse {
+    pub index: u32,
+    pub is_claimed: bool
+}
+
+/// Once a placeholder uri is set, minted tokens ignore the given uri and extension.
+/// They get the placeholder until reveal and `base_uri` + token id afterwards.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RevealInfo {
+    pub placeholder_uri: String,
+    pub base_uri: Option<String>,
+    /// Hex encoded sha256 hash of the final metadata, committed together with the base uri
+    pub provenance_hash: Option<String>,
+    /// Number of tokens still pointing to the placeholder uri
+    pub unrevealed: u32
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SupplyResponse {
+    pub max_tokens: u32,
+    pub minted: u32,
+    pub burned: u32,
+    /// Token ids that can still be minted
+    pub remaining: u32
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {}
+
+// Token ids of sales are strings, and numbers are still accepted from clients built for the old u32 ids
+struct TokenIdVisitor;
+
+impl<'de> Visitor<'de> for TokenIdVisitor {
+    type Value = String;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a token id as a string or a number")
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<String, E> {
+        Ok(value.to_string())
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<String, E> {
+        Ok(value.to_string())
+    }
+}
+
+struct CompatTokenId(String);
+
+impl<'de> Deserialize<'de> for CompatTokenId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(TokenIdVisitor).map(CompatTokenId)
+    }
+}
+
+fn token_id<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    deserializer.deserialize_any(TokenIdVisitor)
+}
+
+fn token_ids<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    let ids = Vec::<CompatTokenId>::deserialize(deserializer)?;
+    Ok(ids.into_iter().map(|id| id.0).collect())
+}
+
+fn optional_token_id<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    let id = Option::<CompatTokenId>::deserialize(deserializer)?;
+    Ok(id.map(|id| id.0))
+}
@@ -2,11 +2,11 @@ use cw721_base::Extension;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{Addr, Uint128};
+use cosmwasm_std::{Addr, Binary, Uint128};
 use cw_storage_plus::Item;
 use cw_utils::{Expiration, Scheduled};
 use cw_storage_plus::{Map};
-use crate::msg::{SaleInfo, Royalty};
+use crate::msg::{SaleInfo, RoyaltySource, Royalty, SupportedDenom, MintPhase, Airdrop, RevealInfo, EscrowBalance, Trade, PauseScope};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
@@ -22,13 +22,110 @@ pub struct Config {
     pub enabled: bool
 }
 
+impl marble_roles::Owned for Config {
+    fn owner(&self) -> &Addr {
+        &self.owner
+    }
+}
+
 
 
 pub const CONFIG_KEY: &str = "config";
 pub const CONFIG: Item<Config> = Item::new(CONFIG_KEY);
 
-pub const SALE_KEY: &str = "sale";
-pub const SALE: Map<String, SaleInfo> = Map::new(SALE_KEY);
+pub const PAUSED_KEY: &str = "paused";
+pub const PAUSED: Item<Vec<PauseScope>> = Item::new(PAUSED_KEY);
+
+// sales listed before external collections, keyed by token id alone and moved into SALE on migrate
+pub const LEGACY_SALE_KEY: &str = "sale";
+pub const LEGACY_SALE: Map<String, SaleInfo> = Map::new(LEGACY_SALE_KEY);
+
+// (cw721 contract, token_id)
+pub const SALE_KEY: &str = "sales";
+pub const SALE: Map<(&Addr, String), SaleInfo> = Map::new(SALE_KEY);
+
+// cw721 contracts other than the configured one whose tokens can be listed
+pub const CW721_WHITELIST_KEY: &str = "cw721_whitelist";
+pub const CW721_WHITELIST: Map<&Addr, RoyaltySource> = Map::new(CW721_WHITELIST_KEY);
+
+// number of tokens minted through this contract, burned ones included
+pub const MINTED_SUPPLY_KEY: &str = "minted_supply";
+pub const MINTED_SUPPLY: Item<u32> = Item::new(MINTED_SUPPLY_KEY);
+
+// denom key -> bid funds held for the auctions in SALE
+pub const ESCROW_KEY: &str = "escrow";
+pub const ESCROW: Map<String, EscrowBalance> = Map::new(ESCROW_KEY);
+
+// raw SALE key of the last sale counted while the escrow ledger is rebuilt, unset otherwise
+pub const ESCROW_REBUILD_KEY: &str = "escrow_rebuild";
+pub const ESCROW_REBUILD: Item<Binary> = Item::new(ESCROW_REBUILD_KEY);
+
+pub const TRADE_COUNT_KEY: &str = "trade_count";
+pub const TRADE_COUNT: Item<u64> = Item::new(TRADE_COUNT_KEY);
+
+pub const TRADES_KEY: &str = "trades";
+pub const TRADES: Map<u64, Trade> = Map::new(TRADES_KEY);
+
+// (cw721 contract, token_id, trade_id)
+pub const TOKEN_TRADES_KEY: &str = "token_trades";
+pub const TOKEN_TRADES: Map<(&Addr, String, u64), bool> = Map::new(TOKEN_TRADES_KEY);
+
+// (cw721 contract, trade_id)
+pub const CW721_TRADES_KEY: &str = "cw721_trades";
+pub const CW721_TRADES: Map<(&Addr, u64), bool> = Map::new(CW721_TRADES_KEY);
+
+// (account, trade_id), indexed for both the seller and the buyer
+pub const ACCOUNT_TRADES_KEY: &str = "account_trades";
+pub const ACCOUNT_TRADES: Map<(&Addr, u64), bool> = Map::new(ACCOUNT_TRADES_KEY);
+
+pub const SUPPORTED_DENOMS_KEY: &str = "supported_denoms";
+pub const SUPPORTED_DENOMS: Map<String, SupportedDenom> = Map::new(SUPPORTED_DENOMS_KEY);
+
+pub const MINT_PHASE_COUNT_KEY: &str = "mint_phase_count";
+pub const MINT_PHASE_COUNT: Item<u32> = Item::new(MINT_PHASE_COUNT_KEY);
+
+pub const MINT_PHASES_KEY: &str = "mint_phases";
+pub const MINT_PHASES: Map<u32, MintPhase> = Map::new(MINT_PHASES_KEY);
+
+// (phase_id, minter) -> number of tokens minted in that phase
+pub const MINTED_KEY: &str = "minted";
+pub const MINTED: Map<(u32, &Addr), u32> = Map::new(MINTED_KEY);
+
+pub const PAYOUT_ADDRESS_KEY: &str = "payout_address";
+pub const PAYOUT_ADDRESS: Item<Addr> = Item::new(PAYOUT_ADDRESS_KEY);
+
+// sha256 of the owner's secret seed, absent when tokens reveal the metadata of their own id
+pub const SHUFFLE_COMMIT_KEY: &str = "shuffle_commit";
+pub const SHUFFLE_COMMIT: Item<[u8; 32]> = Item::new(SHUFFLE_COMMIT_KEY);
+
+// evolving seed of the shuffle, set once the owner reveals the committed seed
+pub const SHUFFLE_SEED_KEY: &str = "shuffle_seed";
+pub const SHUFFLE_SEED: Item<[u8; 32]> = Item::new(SHUFFLE_SEED_KEY);
+
+// number of metadata ids not drawn yet
+pub const SHUFFLE_POOL_KEY: &str = "shuffle_pool";
+pub const SHUFFLE_POOL: Item<u32> = Item::new(SHUFFLE_POOL_KEY);
+
+// Fisher-Yates swap map, position -> metadata id for positions that no longer hold position + 1
+pub const SHUFFLE_SWAP_KEY: &str = "shuffle_swap";
+pub const SHUFFLE_SWAP: Map<u32, u32> = Map::new(SHUFFLE_SWAP_KEY);
+
+pub const REVEAL_KEY: &str = "reveal";
+pub const REVEAL: Item<RevealInfo> = Item::new(REVEAL_KEY);
+
+// token ids minted with the placeholder uri and not revealed yet
+pub const UNREVEALED_KEY: &str = "unrevealed";
+pub const UNREVEALED: Map<u32, bool> = Map::new(UNREVEALED_KEY);
+
+pub const AIRDROP_COUNT_KEY: &str = "airdrop_count";
+pub const AIRDROP_COUNT: Item<u32> = Item::new(AIRDROP_COUNT_KEY);
+
+pub const AIRDROP_KEY: &str = "airdrop";
+pub const AIRDROP: Item<Airdrop> = Item::new(AIRDROP_KEY);
+
+// (airdrop_id, word) -> claimed bits of the entries word * 64 .. word * 64 + 63
+pub const AIRDROP_CLAIMED_KEY: &str = "airdrop_claimed";
+pub const AIRDROP_CLAIMED: Map<(u32, u32), u64> = Map::new(AIRDROP_CLAIMED_KEY);
 // pub const PRICE_KEY: &str = "price";
 // pub const PRICE: Map<u32, Uint128> = Map::new(PRICE_KEY);
 
@@ -1,11 +1,15 @@
 use cosmwasm_std::{
-    to_binary,  Response, StdResult, Uint128, Coin, BankMsg,
-    WasmMsg, WasmQuery, QueryRequest, Addr, Storage, CosmosMsg,  QuerierWrapper, BalanceResponse as NativeBalanceResponse, BankQuery
+    to_binary,  Binary, Response, StdResult, Uint128, Coin, BankMsg,
+    WasmMsg, WasmQuery, QueryRequest, Addr, Storage, CosmosMsg, Order,  QuerierWrapper, BalanceResponse as NativeBalanceResponse, BankQuery
 };
+use cw_storage_plus::{Bound, PrimaryKey};
 use cw20::{Balance, Cw20ExecuteMsg, Denom, BalanceResponse as CW20BalanceResponse, Cw20QueryMsg};
 use crate::error::ContractError;
-use crate::state::CONFIG;
-use crate::msg::Royalty;
+use crate::state::{Config, CONFIG, PAUSED, SUPPORTED_DENOMS, MINTED_SUPPLY, ESCROW, SALE, LEGACY_SALE, CW721_WHITELIST, REVEAL, UNREVEALED, ESCROW_REBUILD};
+use crate::msg::{DurationType, EscrowBalance, PauseScope, Role, SaleInfo, Royalty, SaleItem, SaleType, SupportedDenom};
+use cw721::NftInfoResponse;
+use cw721_base::Extension;
+use sha2::Digest;
 use wasmswap::msg::{ExecuteMsg as WasmswapExecuteMsg, QueryMsg as WasmswapQueryMsg, Token1ForToken2PriceResponse, Token2ForToken1PriceResponse, InfoResponse as WasmswapInfoResponse, TokenSelect};
 
 pub const MAX_LIMIT: u32 = 30;
@@ -15,6 +19,25 @@ pub const MAX_ORDER: u64 = 10;
 pub fn multiple() -> Uint128 { Uint128::from(100u128) }
 pub fn decimal() -> Uint128 { Uint128::from(1000000u128) }
 
+// Token ids run from 1 to max_tokens, so unused_token_id is max_tokens + 1 once all are out
+pub fn remaining_tokens(
+    config: &Config
+) -> u32 {
+    if config.unused_token_id > config.max_tokens {
+        0
+    } else {
+        config.max_tokens - config.unused_token_id + 1
+    }
+}
+
+// Contracts minted before the counter existed had handed out every id below unused_token_id
+pub fn minted_supply(
+    storage: &dyn Storage,
+    config: &Config
+) -> StdResult<u32> {
+    Ok(MINTED_SUPPLY.may_load(storage)?.unwrap_or(config.unused_token_id - 1))
+}
+
 pub fn check_enabled(
     storage: &mut dyn Storage,
 ) -> Result<Response, ContractError> {
@@ -25,6 +48,24 @@ pub fn check_enabled(
     Ok(Response::new().add_attribute("action", "check_enabled"))
 }
 
+pub fn check_not_paused(
+    storage: &mut dyn Storage,
+    scope: PauseScope
+) -> Result<Response, ContractError> {
+    check_enabled(storage)?;
+    if PAUSED.may_load(storage)?.unwrap_or_default().contains(&scope) {
+        return Err(ContractError::Paused {})
+    }
+    Ok(Response::new().add_attribute("action", "check_not_paused"))
+}
+
+// Emergency mode, trading is halted and the exits skip the checks that protect a running sale
+pub fn is_disabled(
+    storage: &dyn Storage
+) -> StdResult<bool> {
+    Ok(!CONFIG.load(storage)?.enabled)
+}
+
 pub fn check_owner(
     storage: &mut dyn Storage,
     address: Addr
@@ -37,6 +78,21 @@ pub fn check_owner(
     Ok(Response::new().add_attribute("action", "check_owner"))
 }
 
+// Reveal edits the placeholder tokens on cw721, which a frozen token would refuse for good
+pub fn check_no_pending_reveal(
+    storage: &dyn Storage,
+    token_id: Option<u32>
+) -> Result<(), ContractError> {
+    let pending = match token_id {
+        Some(token_id) => UNREVEALED.has(storage, token_id),
+        None => REVEAL.may_load(storage)?.is_some_and(|reveal| reveal.unrevealed > 0)
+    };
+    if pending {
+        return Err(ContractError::RevealPending {});
+    }
+    Ok(())
+}
+
 pub fn execute_update_owner(
     storage: &mut dyn Storage,
     address: Addr,
@@ -58,8 +114,7 @@ pub fn execute_update_enabled (
     address: Addr,
     enabled: bool
 ) -> Result<Response, ContractError> {
-    // authorize owner
-    check_owner(storage, address)?;
+    marble_roles::check_role(storage, &CONFIG, &address, Role::Pauser)?;
     
     CONFIG.update(storage, |mut exists| -> StdResult<_> {
         exists.enabled = enabled;
@@ -69,14 +124,33 @@ pub fn execute_update_enabled (
     Ok(Response::new().add_attribute("action", "update_enabled"))
 }
 
+pub fn execute_set_paused (
+    storage: &mut dyn Storage,
+    address: Addr,
+    scope: PauseScope,
+    paused: bool
+) -> Result<Response, ContractError> {
+    marble_roles::check_role(storage, &CONFIG, &address, Role::Pauser)?;
+
+    let mut list = PAUSED.may_load(storage)?.unwrap_or_default();
+    list.retain(|item| *item != scope);
+    if paused {
+        list.push(scope);
+    }
+    PAUSED.save(storage, &list)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_paused")
+        .add_attribute("paused", paused.to_string()))
+}
+
 pub fn execute_update_royalties (
     storage: &mut dyn Storage,
     address: Addr,
     maximum_royalty_fee: u32,
     royalties: Vec<Royalty>
 ) -> Result<Response, ContractError> {
-    // authorize owner
-    check_owner(storage, address)?;
+    marble_roles::check_role(storage, &CONFIG, &address, Role::RoyaltyManager)?;
 
     let mut sum = 0;
     for item in royalties.clone() {
@@ -96,6 +170,43 @@ pub fn execute_update_royalties (
     Ok(Response::new().add_attribute("action", "update_royalties"))
 }
 
+pub fn decode_hash(
+    hash: &str
+) -> Result<[u8; 32], ContractError> {
+    let mut buf = [0u8; 32];
+    hex::decode_to_slice(hash, &mut buf).map_err(|_| ContractError::WrongLength {})?;
+    Ok(buf)
+}
+
+// Proof nodes are hex encoded sha256 hashes, and each pair is hashed in sorted order
+pub fn verify_merkle_proof(
+    merkle_root: &str,
+    leaf: &[u8],
+    proof: Vec<String>
+) -> Result<(), ContractError> {
+    let leaf_hash: [u8; 32] = sha2::Sha256::digest(leaf).into();
+
+    let hash = proof.into_iter().try_fold(leaf_hash, |hash, node| -> Result<[u8; 32], ContractError> {
+        let mut hashes = [hash, decode_hash(&node)?];
+        hashes.sort_unstable();
+        Ok(sha2::Sha256::digest(&hashes.concat()).into())
+    })?;
+
+    if decode_hash(merkle_root)? != hash {
+        return Err(ContractError::VerificationFailed {});
+    }
+    Ok(())
+}
+
+// sha256 of the json encoded uri and extension, used to track metadata edits
+pub fn metadata_hash(
+    token_uri: Option<String>,
+    extension: Extension
+) -> StdResult<String> {
+    let info = NftInfoResponse { token_uri, extension };
+    Ok(hex::encode(sha2::Sha256::digest(to_binary(&info)?.as_slice())))
+}
+
 pub fn check_token_and_pool (
     querier: QuerierWrapper,
     denom: Denom,
@@ -219,6 +330,251 @@ pub fn get_swap_amount_and_denom_and_message(
 }
 
 
+pub fn denom_key(
+    denom: &Denom
+) -> String {
+    match denom {
+        Denom::Native(native_str) => native_str.clone(),
+        Denom::Cw20(cw20_address) => cw20_address.to_string()
+    }
+}
+
+pub fn check_supported_denom(
+    storage: &dyn Storage,
+    denom: &Denom
+) -> Result<SupportedDenom, ContractError> {
+    match SUPPORTED_DENOMS.may_load(storage, denom_key(denom))? {
+        Some(supported) if supported.enabled => Ok(supported),
+        _ => Err(ContractError::UnsupportedDenom {})
+    }
+}
+
+// Sales listed before SaleInfo kept its cw721 contract all came from the configured one
+pub fn sale_cw721_address(
+    storage: &dyn Storage,
+    sale_info: &SaleInfo
+) -> Result<Addr, ContractError> {
+    match sale_info.cw721_address.clone() {
+        Some(cw721_address) => Ok(cw721_address),
+        None => CONFIG.load(storage)?.cw721_address.ok_or(ContractError::Uninitialized {})
+    }
+}
+
+pub fn check_sale_item(
+    storage: &dyn Storage,
+    item: &SaleItem
+) -> Result<(), ContractError> {
+    if item.token_id.is_empty() {
+        return Err(ContractError::InvalidTokenId {});
+    }
+    check_supported_denom(storage, &item.denom)?;
+
+    if item.sale_type == SaleType::Fixed && item.duration_type != DurationType::Fixed {
+        return Err(ContractError::InvalidSaleType {});
+    }
+
+    match item.duration_type.clone() {
+        DurationType::Time(start, end) => {
+            if start >= end {
+                return Err(ContractError::DurationIncorrect {});
+            }
+        },
+        DurationType::Fixed => {},
+        DurationType::Bid(_count) => {}
+    }
+    Ok(())
+}
+
+// While the ledger is rebuilt, sales the rebuild has not reached yet are left to it
+fn escrow_counted(
+    storage: &dyn Storage,
+    sale_key: (&Addr, &str)
+) -> StdResult<bool> {
+    Ok(match ESCROW_REBUILD.may_load(storage)? {
+        Some(last_key) => (sale_key.0, sale_key.1.to_string()).joined_key() <= last_key.to_vec(),
+        None => true
+    })
+}
+
+pub fn add_escrow(
+    storage: &mut dyn Storage,
+    sale_key: (&Addr, &str),
+    denom: &Denom,
+    amount: Uint128
+) -> StdResult<()> {
+    if !escrow_counted(storage, sale_key)? {
+        return Ok(());
+    }
+    credit_escrow(storage, denom, amount)
+}
+
+fn credit_escrow(
+    storage: &mut dyn Storage,
+    denom: &Denom,
+    amount: Uint128
+) -> StdResult<()> {
+    let mut escrow = ESCROW.may_load(storage, denom_key(denom))?
+        .unwrap_or(EscrowBalance { denom: denom.clone(), amount: Uint128::zero() });
+    escrow.amount = escrow.amount.checked_add(amount)?;
+    ESCROW.save(storage, denom_key(denom), &escrow)
+}
+
+pub fn remove_escrow(
+    storage: &mut dyn Storage,
+    sale_key: (&Addr, &str),
+    denom: &Denom,
+    amount: Uint128
+) -> StdResult<()> {
+    if !escrow_counted(storage, sale_key)? {
+        return Ok(());
+    }
+    let mut escrow = ESCROW.may_load(storage, denom_key(denom))?
+        .unwrap_or(EscrowBalance { denom: denom.clone(), amount: Uint128::zero() });
+    // a ledger short of its bids must never block refunds, VerifyEscrow shows the gap instead
+    escrow.amount = escrow.amount.saturating_sub(amount);
+    ESCROW.save(storage, denom_key(denom), &escrow)
+}
+
+// Empties the ledger so rebuild_escrow can sum the bids of every sale again, for contracts
+// that took bids before the ledger existed. One entry per denom, so this stays cheap
+pub fn start_escrow_rebuild(
+    storage: &mut dyn Storage
+) -> StdResult<()> {
+    let keys: StdResult<Vec<String>> = ESCROW.keys(storage, None, None, Order::Ascending).collect();
+    for key in keys? {
+        ESCROW.remove(storage, key);
+    }
+    ESCROW_REBUILD.save(storage, &Binary::default())
+}
+
+// Counts the bids of up to limit more sales, returns whether every sale is counted
+pub fn rebuild_escrow(
+    storage: &mut dyn Storage,
+    limit: usize
+) -> StdResult<bool> {
+    if ESCROW_REBUILD.may_load(storage)?.is_none() {
+        start_escrow_rebuild(storage)?;
+    }
+    let last_key = ESCROW_REBUILD.load(storage)?;
+    let start = (!last_key.is_empty()).then(|| Bound::Exclusive(last_key.to_vec()));
+
+    let mut range = SALE.range_raw(storage, start, None, Order::Ascending);
+    let sales = range.by_ref()
+        .take(limit.max(1))
+        .collect::<StdResult<Vec<_>>>()?;
+    // done only once no sale is left past this batch
+    let complete = range.next().is_none();
+    drop(range);
+
+    for (_key, sale_info) in sales.iter() {
+        for request in sale_info.requests.iter() {
+            credit_escrow(storage, &sale_info.denom, request.price)?;
+        }
+    }
+
+    match sales.last() {
+        Some((key, _sale_info)) if !complete => {
+            ESCROW_REBUILD.save(storage, &Binary::from(key.clone()))?;
+        },
+        _ => ESCROW_REBUILD.remove(storage)
+    }
+    Ok(complete)
+}
+
+// Moves the sales keyed by token id alone under the cw721 contract holding them, and rewrites
+// every sale so its token id is stored as a string
+pub fn migrate_sales(
+    storage: &mut dyn Storage
+) -> Result<(), ContractError> {
+    let sales: StdResult<Vec<_>> = LEGACY_SALE.range(storage, None, None, Order::Ascending).collect();
+    for (key, mut sale_info) in sales? {
+        let cw721_address = sale_cw721_address(storage, &sale_info)?;
+        sale_info.cw721_address = Some(cw721_address.clone());
+        SALE.save(storage, (&cw721_address, key.clone()), &sale_info)?;
+        LEGACY_SALE.remove(storage, key);
+    }
+
+    let sales: StdResult<Vec<_>> = SALE.range(storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_key, sale_info)| sale_info))
+        .collect();
+    for sale_info in sales? {
+        let cw721_address = sale_cw721_address(storage, &sale_info)?;
+        SALE.save(storage, (&cw721_address, sale_info.token_id.clone()), &sale_info)?;
+    }
+    Ok(())
+}
+
+// Sales target the collection's own cw721 contract unless another one is named
+pub fn resolve_cw721_address(
+    storage: &dyn Storage,
+    cw721_address: Option<Addr>
+) -> Result<Addr, ContractError> {
+    match cw721_address {
+        Some(cw721_address) => Ok(cw721_address),
+        None => CONFIG.load(storage)?.cw721_address.ok_or(ContractError::Uninitialized {})
+    }
+}
+
+pub fn check_listable_cw721(
+    storage: &dyn Storage,
+    cw721_address: &Addr
+) -> Result<(), ContractError> {
+    if CONFIG.load(storage)?.cw721_address.as_ref() == Some(cw721_address)
+        || CW721_WHITELIST.has(storage, cw721_address) {
+        return Ok(());
+    }
+    Err(ContractError::InvalidCw721Token {})
+}
+
+fn swap_pool(
+    storage: &dyn Storage,
+    denom: &Denom
+) -> Result<Option<Addr>, ContractError> {
+    match check_supported_denom(storage, denom) {
+        Ok(supported) => Ok(supported.pool_address),
+        Err(ContractError::UnsupportedDenom {}) => Ok(None),
+        Err(err) => Err(err)
+    }
+}
+
+// Quotes and builds the swaps converting `amount` of `input_denom` into `output_denom`.
+// A route is either one supported denom's pool pairing both denoms, or two such pools
+// sharing a common denom (e.g. ATOM -> JUNO -> OSMO).
+pub fn get_swap_route_messages(
+    querier: QuerierWrapper,
+    storage: &dyn Storage,
+    input_denom: Denom,
+    amount: Uint128,
+    output_denom: Denom
+) -> Result<(Uint128, Vec<CosmosMsg>), ContractError> {
+    let input_pool = swap_pool(storage, &input_denom)?;
+    let output_pool = swap_pool(storage, &output_denom)?;
+
+    let first_pool = match (input_pool, output_pool.clone()) {
+        (Some(pool), _) => pool,
+        (None, Some(pool)) => pool,
+        (None, None) => return Err(ContractError::NoSwapRoute {})
+    };
+
+    let (mut swap_amount, mut swap_denom, mut messages) = get_swap_amount_and_denom_and_message(querier, first_pool.clone(), input_denom, amount)?;
+
+    if swap_denom != output_denom {
+        let second_pool = match output_pool {
+            Some(pool) if pool != first_pool => pool,
+            _ => return Err(ContractError::NoSwapRoute {})
+        };
+        let (hop_amount, hop_denom, hop_messages) = get_swap_amount_and_denom_and_message(querier, second_pool, swap_denom, swap_amount)?;
+        swap_amount = hop_amount;
+        swap_denom = hop_denom;
+        messages.extend(hop_messages);
+    }
+
+    if swap_denom != output_denom {
+        return Err(ContractError::NoSwapRoute {});
+    }
+    Ok((swap_amount, messages))
+}
+
 pub fn swap_token_messages(
     denom: Denom,
     input_token: TokenSelect,
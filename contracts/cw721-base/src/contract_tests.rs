@@ -1,14 +1,19 @@
 #![cfg(test)]
 use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-use cosmwasm_std::{from_binary, to_binary, CosmosMsg, DepsMut, Empty, Response, WasmMsg};
+use cosmwasm_std::{
+    attr, from_binary, to_binary, Addr, CosmosMsg, DepsMut, Empty, Response, WasmMsg,
+};
 
 use cw721::{
     Approval, ApprovalResponse, ContractInfoResponse, Cw721Query, Cw721ReceiveMsg, Expiration,
-    NftInfoResponse, OperatorsResponse, OwnerOfResponse,
+    NftInfoResponse, OperatorsResponse, OwnerOfResponse, TokensResponse,
 };
 
+use crate::msg::{TokenWithInfo, TokensWithInfoResponse, TraitCount};
 use crate::{
-    ContractError, Cw721Contract, ExecuteMsg, Extension, InstantiateMsg, MintMsg, QueryMsg,
+    ContractError, Cw721Contract, EditMsg, ExecuteMsg, Extension, FrozenResponse, InstantiateMsg,
+    Metadata, MintMsg, ProvenanceResponse, QueryMsg, Trait, TransferHookMsg, TransferPolicy,
+    TransferPolicyResponse, TransferRule,
 };
 
 const MINTER: &str = "merlin";
@@ -21,6 +26,8 @@ fn setup_contract(deps: DepsMut<'_>) -> Cw721Contract<'static, Extension, Empty>
         name: CONTRACT_NAME.to_string(),
         symbol: SYMBOL.to_string(),
         minter: String::from(MINTER),
+        soulbound: false,
+        index_traits: false,
     };
     let info = mock_info("creator", &[]);
     let res = contract.instantiate(deps, mock_env(), info, msg).unwrap();
@@ -37,6 +44,8 @@ fn proper_instantiation() {
         name: CONTRACT_NAME.to_string(),
         symbol: SYMBOL.to_string(),
         minter: String::from(MINTER),
+        soulbound: false,
+        index_traits: false,
     };
     let info = mock_info("creator", &[]);
 
@@ -79,6 +88,7 @@ fn minting() {
         owner: String::from("medusa"),
         token_uri: Some(token_uri.clone()),
         extension: None,
+        soulbound: false,
     });
 
     // random cannot mint
@@ -131,6 +141,7 @@ fn minting() {
         owner: String::from("hercules"),
         token_uri: None,
         extension: None,
+        soulbound: false,
     });
 
     let allowed = mock_info(MINTER, &[]);
@@ -145,6 +156,405 @@ fn minting() {
     assert_eq!(vec![token_id], tokens.tokens);
 }
 
+#[test]
+fn editing() {
+    let mut deps = mock_dependencies();
+    let contract = setup_contract(deps.as_mut());
+
+    let token_id = "petrify".to_string();
+    let mint_msg = ExecuteMsg::Mint(MintMsg::<Extension> {
+        token_id: token_id.clone(),
+        owner: String::from("medusa"),
+        token_uri: Some("ipfs://hidden".to_string()),
+        extension: None,
+        soulbound: false,
+    });
+    let allowed = mock_info(MINTER, &[]);
+    contract
+        .execute(deps.as_mut(), mock_env(), allowed, mint_msg)
+        .unwrap();
+
+    let edit_msg = ExecuteMsg::Edit(EditMsg::<Extension> {
+        token_id: token_id.clone(),
+        token_uri: Some("ipfs://revealed/petrify".to_string()),
+        extension: None,
+    });
+
+    // the owner of the token cannot edit it
+    let owner = mock_info("medusa", &[]);
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), owner, edit_msg.clone())
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // minter can edit
+    let allowed = mock_info(MINTER, &[]);
+    contract
+        .execute(deps.as_mut(), mock_env(), allowed, edit_msg)
+        .unwrap();
+
+    let info = contract.nft_info(deps.as_ref(), token_id.clone()).unwrap();
+    assert_eq!(
+        info,
+        NftInfoResponse::<Extension> {
+            token_uri: Some("ipfs://revealed/petrify".to_string()),
+            extension: None,
+        }
+    );
+
+    // ownership is untouched
+    let owner = contract
+        .owner_of(deps.as_ref(), mock_env(), token_id, true)
+        .unwrap();
+    assert_eq!(owner.owner, String::from("medusa"));
+}
+
+#[test]
+fn freezing_metadata() {
+    let mut deps = mock_dependencies();
+    let contract = setup_contract(deps.as_mut());
+
+    for token_id in ["petrify", "enchant"] {
+        let mint_msg = ExecuteMsg::Mint(MintMsg::<Extension> {
+            token_id: token_id.to_string(),
+            owner: String::from("medusa"),
+            token_uri: None,
+            extension: None,
+            soulbound: false,
+        });
+        contract
+            .execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg)
+            .unwrap();
+    }
+    let edit_msg = |token_id: &str| {
+        ExecuteMsg::Edit(EditMsg::<Extension> {
+            token_id: token_id.to_string(),
+            token_uri: Some("ipfs://edited".to_string()),
+            extension: None,
+        })
+    };
+    let set_frozen = |token_id: Option<&str>, frozen: bool| ExecuteMsg::SetFrozen {
+        token_id: token_id.map(String::from),
+        frozen,
+    };
+
+    // only the minter can freeze
+    let err = contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("medusa", &[]),
+            set_frozen(Some("petrify"), true),
+        )
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // freezing one token leaves the others editable
+    contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MINTER, &[]),
+            set_frozen(Some("petrify"), true),
+        )
+        .unwrap();
+    let err = contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MINTER, &[]),
+            edit_msg("petrify"),
+        )
+        .unwrap_err();
+    assert_eq!(err, ContractError::Frozen {});
+    contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MINTER, &[]),
+            edit_msg("enchant"),
+        )
+        .unwrap();
+
+    let res: FrozenResponse = from_binary(
+        &contract
+            .query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::Frozen {
+                    token_id: Some("petrify".to_string()),
+                },
+            )
+            .unwrap(),
+    )
+    .unwrap();
+    assert!(res.frozen);
+
+    // freezing the contract covers every token
+    contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MINTER, &[]),
+            set_frozen(None, true),
+        )
+        .unwrap();
+    let err = contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MINTER, &[]),
+            edit_msg("enchant"),
+        )
+        .unwrap_err();
+    assert_eq!(err, ContractError::Frozen {});
+
+    contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MINTER, &[]),
+            set_frozen(None, false),
+        )
+        .unwrap();
+    contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MINTER, &[]),
+            set_frozen(Some("petrify"), false),
+        )
+        .unwrap();
+    contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MINTER, &[]),
+            edit_msg("petrify"),
+        )
+        .unwrap();
+}
+
+#[test]
+fn freezing_metadata_permanently() {
+    let mut deps = mock_dependencies();
+    let contract = setup_contract(deps.as_mut());
+
+    for token_id in ["petrify", "enchant"] {
+        let mint_msg = ExecuteMsg::Mint(MintMsg::<Extension> {
+            token_id: token_id.to_string(),
+            owner: String::from("medusa"),
+            token_uri: Some(format!("ipfs://{}", token_id)),
+            extension: None,
+            soulbound: false,
+        });
+        contract
+            .execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg)
+            .unwrap();
+    }
+
+    let err = contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("medusa", &[]),
+            ExecuteMsg::FreezeMetadata { limit: None },
+        )
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MINTER, &[]),
+            ExecuteMsg::FreezeMetadata { limit: None },
+        )
+        .unwrap();
+    let err = contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MINTER, &[]),
+            ExecuteMsg::FreezeMetadata { limit: None },
+        )
+        .unwrap_err();
+    assert_eq!(err, ContractError::MetadataFrozen {});
+
+    // tokens are hashed in token_id order, each step over the previous digest
+    let expected = {
+        use sha2::Digest;
+        let first = sha2::Sha256::digest(b"enchant:ipfs://enchant\n");
+        hex::encode(sha2::Sha256::digest(
+            &[&first[..], &b"petrify:ipfs://petrify\n"[..]].concat(),
+        ))
+    };
+    let res: ProvenanceResponse = from_binary(
+        &contract
+            .query(deps.as_ref(), mock_env(), QueryMsg::Provenance {})
+            .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.provenance_hash, Some(expected));
+    let res: FrozenResponse = from_binary(
+        &contract
+            .query(deps.as_ref(), mock_env(), QueryMsg::MetadataFrozen {})
+            .unwrap(),
+    )
+    .unwrap();
+    assert!(res.frozen);
+
+    // the reversible freeze can not lift it
+    contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MINTER, &[]),
+            ExecuteMsg::SetFrozen {
+                token_id: None,
+                frozen: false,
+            },
+        )
+        .unwrap();
+    let edit_msg = ExecuteMsg::Edit(EditMsg::<Extension> {
+        token_id: "petrify".to_string(),
+        token_uri: None,
+        extension: None,
+    });
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), edit_msg)
+        .unwrap_err();
+    assert_eq!(err, ContractError::Frozen {});
+
+    // neither can a new minter, and no more tokens can be added
+    contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MINTER, &[]),
+            ExecuteMsg::ChangeMinter {
+                new_minter: Addr::unchecked("hercules"),
+            },
+        )
+        .unwrap();
+    let mint_msg = ExecuteMsg::Mint(MintMsg::<Extension> {
+        token_id: "curse".to_string(),
+        owner: String::from("medusa"),
+        token_uri: None,
+        extension: None,
+        soulbound: false,
+    });
+    let err = contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("hercules", &[]),
+            mint_msg,
+        )
+        .unwrap_err();
+    assert_eq!(err, ContractError::MetadataFrozen {});
+}
+
+#[test]
+fn freezing_metadata_in_steps() {
+    let mut deps = mock_dependencies();
+    let contract = setup_contract(deps.as_mut());
+    let minter = mock_info(MINTER, &[]);
+
+    for token_id in ["a", "b", "c"] {
+        let mint_msg = ExecuteMsg::Mint(MintMsg::<Extension> {
+            token_id: token_id.to_string(),
+            owner: String::from("medusa"),
+            token_uri: Some(format!("ipfs://{}", token_id)),
+            extension: None,
+            soulbound: false,
+        });
+        contract
+            .execute(deps.as_mut(), mock_env(), minter.clone(), mint_msg)
+            .unwrap();
+    }
+    let freeze = ExecuteMsg::FreezeMetadata { limit: Some(2) };
+    let provenance = |deps: &cosmwasm_std::OwnedDeps<_, _, _>| {
+        let res: ProvenanceResponse = from_binary(
+            &contract
+                .query(deps.as_ref(), mock_env(), QueryMsg::Provenance {})
+                .unwrap(),
+        )
+        .unwrap();
+        res.provenance_hash
+    };
+
+    let res = contract
+        .execute(deps.as_mut(), mock_env(), minter.clone(), freeze.clone())
+        .unwrap();
+    assert!(res.attributes.contains(&attr("complete", "false")));
+    assert_eq!(provenance(&deps), None);
+
+    // minting and editing stop with the first step, burning until the last one
+    let mint_msg = ExecuteMsg::Mint(MintMsg::<Extension> {
+        token_id: "d".to_string(),
+        owner: String::from("medusa"),
+        token_uri: None,
+        extension: None,
+        soulbound: false,
+    });
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), minter.clone(), mint_msg)
+        .unwrap_err();
+    assert_eq!(err, ContractError::MetadataFrozen {});
+    let edit_msg = ExecuteMsg::Edit(EditMsg::<Extension> {
+        token_id: "c".to_string(),
+        token_uri: None,
+        extension: None,
+    });
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), minter.clone(), edit_msg)
+        .unwrap_err();
+    assert_eq!(err, ContractError::Frozen {});
+    let burn_msg = ExecuteMsg::Burn {
+        token_id: "c".to_string(),
+    };
+    let err = contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("medusa", &[]),
+            burn_msg.clone(),
+        )
+        .unwrap_err();
+    assert_eq!(err, ContractError::FreezeInProgress {});
+
+    let res = contract
+        .execute(deps.as_mut(), mock_env(), minter.clone(), freeze.clone())
+        .unwrap();
+    assert!(res.attributes.contains(&attr("complete", "true")));
+
+    // same chain as hashing every token at once
+    let expected = {
+        use sha2::Digest;
+        let mut hash = vec![];
+        for token_id in ["a", "b", "c"] {
+            let mut hasher = sha2::Sha256::new();
+            hasher.update(&hash);
+            hasher.update(format!("{}:ipfs://{}\n", token_id, token_id));
+            hash = hasher.finalize().to_vec();
+        }
+        hex::encode(hash)
+    };
+    assert_eq!(provenance(&deps), Some(expected));
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), minter, freeze)
+        .unwrap_err();
+    assert_eq!(err, ContractError::MetadataFrozen {});
+    contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("medusa", &[]),
+            burn_msg,
+        )
+        .unwrap();
+}
+
 #[test]
 fn burning() {
     let mut deps = mock_dependencies();
@@ -158,6 +568,7 @@ fn burning() {
         owner: MINTER.to_string(),
         token_uri: Some(token_uri),
         extension: None,
+        soulbound: false,
     });
 
     let burn_msg = ExecuteMsg::Burn { token_id };
@@ -208,6 +619,7 @@ fn transferring_nft() {
         owner: String::from("venus"),
         token_uri: Some(token_uri),
         extension: None,
+        soulbound: false,
     });
 
     let minter = mock_info(MINTER, &[]);
@@ -248,6 +660,328 @@ fn transferring_nft() {
     );
 }
 
+#[test]
+fn transfer_policy() {
+    let mut deps = mock_dependencies();
+    let contract = setup_contract(deps.as_mut());
+
+    let token_id = "melt".to_string();
+    let mint_msg = ExecuteMsg::Mint(MintMsg::<Extension> {
+        token_id: token_id.clone(),
+        owner: String::from("venus"),
+        token_uri: None,
+        extension: None,
+        soulbound: false,
+    });
+    let minter = mock_info(MINTER, &[]);
+    contract
+        .execute(deps.as_mut(), mock_env(), minter.clone(), mint_msg)
+        .unwrap();
+
+    let policy = |rule: TransferRule, allow_gifts: bool| ExecuteMsg::SetTransferPolicy {
+        policy: Some(TransferPolicy { rule, allow_gifts }),
+    };
+    let operators = TransferRule::Operators {
+        operators: vec![Addr::unchecked("market")],
+    };
+
+    // only the minter sets the policy
+    let err = contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("venus", &[]),
+            policy(operators.clone(), false),
+        )
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            minter.clone(),
+            policy(operators.clone(), false),
+        )
+        .unwrap();
+    let res: TransferPolicyResponse = from_binary(
+        &contract
+            .query(deps.as_ref(), mock_env(), QueryMsg::TransferPolicy {})
+            .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.policy,
+        Some(TransferPolicy {
+            rule: operators.clone(),
+            allow_gifts: false
+        })
+    );
+
+    // owners can't hand tokens to each other, only to the allowlisted operator
+    let transfer = |recipient: &str| ExecuteMsg::TransferNft {
+        recipient: recipient.to_string(),
+        token_id: token_id.clone(),
+    };
+    let venus = mock_info("venus", &[]);
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), venus.clone(), transfer("mars"))
+        .unwrap_err();
+    assert_eq!(err, ContractError::TransferRestricted {});
+    contract
+        .execute(deps.as_mut(), mock_env(), venus.clone(), transfer("market"))
+        .unwrap();
+    contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("market", &[]),
+            transfer("venus"),
+        )
+        .unwrap();
+
+    // gifts to existing holders skip the operators when allowed
+    contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            minter.clone(),
+            policy(operators, true),
+        )
+        .unwrap();
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), venus.clone(), transfer("mars"))
+        .unwrap_err();
+    assert_eq!(err, ContractError::TransferRestricted {});
+    contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            minter.clone(),
+            ExecuteMsg::Mint(MintMsg::<Extension> {
+                token_id: "mars".to_string(),
+                owner: String::from("mars"),
+                token_uri: None,
+                extension: None,
+                soulbound: false,
+            }),
+        )
+        .unwrap();
+    contract
+        .execute(deps.as_mut(), mock_env(), venus, transfer("mars"))
+        .unwrap();
+
+    // the hook is called with the transfer and can still reject it by failing
+    contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            minter.clone(),
+            policy(
+                TransferRule::Hook {
+                    contract: Addr::unchecked("hook"),
+                },
+                false,
+            ),
+        )
+        .unwrap();
+    let res = contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("mars", &[]),
+            transfer("venus"),
+        )
+        .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from("hook"),
+            msg: to_binary(&TransferHookMsg::CheckTransfer {
+                sender: String::from("mars"),
+                owner: String::from("mars"),
+                recipient: String::from("venus"),
+                token_id: token_id.clone(),
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+
+    contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            minter,
+            ExecuteMsg::SetTransferPolicy { policy: None },
+        )
+        .unwrap();
+    let res = contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("venus", &[]),
+            transfer("mars"),
+        )
+        .unwrap();
+    assert!(res.messages.is_empty());
+}
+
+#[test]
+fn soulbound_tokens() {
+    let mut deps = mock_dependencies();
+    let contract = setup_contract(deps.as_mut());
+    let minter = mock_info(MINTER, &[]);
+    let venus = mock_info("venus", &[]);
+
+    let mint = |token_id: &str, soulbound: bool| {
+        ExecuteMsg::Mint(MintMsg::<Extension> {
+            token_id: token_id.to_string(),
+            owner: String::from("venus"),
+            token_uri: None,
+            extension: None,
+            soulbound,
+        })
+    };
+    contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            minter.clone(),
+            mint("badge", true),
+        )
+        .unwrap();
+    contract
+        .execute(deps.as_mut(), mock_env(), minter, mint("coin", false))
+        .unwrap();
+
+    let res: crate::msg::NftInfoResponse<Extension> = from_binary(
+        &contract
+            .query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::NftInfo {
+                    token_id: "badge".to_string(),
+                },
+            )
+            .unwrap(),
+    )
+    .unwrap();
+    assert!(res.soulbound);
+    // still readable as a plain cw721 response
+    let res: NftInfoResponse<Extension> = from_binary(
+        &contract
+            .query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::NftInfo {
+                    token_id: "coin".to_string(),
+                },
+            )
+            .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.token_uri, None);
+
+    // the badge can not be moved or approved
+    let transfer = ExecuteMsg::TransferNft {
+        recipient: String::from("random"),
+        token_id: "badge".to_string(),
+    };
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), venus.clone(), transfer)
+        .unwrap_err();
+    assert_eq!(err, ContractError::Soulbound {});
+    let send = ExecuteMsg::SendNft {
+        contract: String::from("another_contract"),
+        token_id: "badge".to_string(),
+        msg: to_binary("You now have the melting power").unwrap(),
+    };
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), venus.clone(), send)
+        .unwrap_err();
+    assert_eq!(err, ContractError::Soulbound {});
+    let approve = |token_id: &str| ExecuteMsg::Approve {
+        spender: String::from("random"),
+        token_id: token_id.to_string(),
+        expires: None,
+    };
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), venus.clone(), approve("badge"))
+        .unwrap_err();
+    assert_eq!(err, ContractError::Soulbound {});
+
+    // an operator gains nothing over the badge
+    let approve_all = ExecuteMsg::ApproveAll {
+        operator: String::from("random"),
+        expires: None,
+    };
+    contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            venus.clone(),
+            approve_all.clone(),
+        )
+        .unwrap();
+    let transfer = ExecuteMsg::TransferNft {
+        recipient: String::from("random"),
+        token_id: "badge".to_string(),
+    };
+    let err = contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("random", &[]),
+            transfer,
+        )
+        .unwrap_err();
+    assert_eq!(err, ContractError::Soulbound {});
+
+    // other tokens are unaffected
+    contract
+        .execute(deps.as_mut(), mock_env(), venus.clone(), approve("coin"))
+        .unwrap();
+
+    // the owner can still burn it
+    let burn = ExecuteMsg::Burn {
+        token_id: "badge".to_string(),
+    };
+    contract
+        .execute(deps.as_mut(), mock_env(), venus.clone(), burn)
+        .unwrap();
+    assert!(!contract.soulbound_tokens.has(&deps.storage, "badge"));
+
+    // a soulbound contract refuses operators too
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: String::from(MINTER),
+        soulbound: true,
+        index_traits: false,
+    };
+    contract
+        .instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg)
+        .unwrap();
+    contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MINTER, &[]),
+            mint("coin", false),
+        )
+        .unwrap();
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), venus.clone(), approve("coin"))
+        .unwrap_err();
+    assert_eq!(err, ContractError::Soulbound {});
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), venus, approve_all)
+        .unwrap_err();
+    assert_eq!(err, ContractError::Soulbound {});
+}
+
 #[test]
 fn sending_nft() {
     let mut deps = mock_dependencies();
@@ -262,6 +996,7 @@ fn sending_nft() {
         owner: String::from("venus"),
         token_uri: Some(token_uri),
         extension: None,
+        soulbound: false,
     });
 
     let minter = mock_info(MINTER, &[]);
@@ -328,6 +1063,7 @@ fn approving_revoking() {
         owner: String::from("demeter"),
         token_uri: Some(token_uri),
         extension: None,
+        soulbound: false,
     });
 
     let minter = mock_info(MINTER, &[]);
@@ -455,6 +1191,7 @@ fn approving_all_revoking_all() {
         owner: String::from("demeter"),
         token_uri: Some(token_uri1),
         extension: None,
+        soulbound: false,
     });
 
     let minter = mock_info(MINTER, &[]);
@@ -467,6 +1204,7 @@ fn approving_all_revoking_all() {
         owner: String::from("demeter"),
         token_uri: Some(token_uri2),
         extension: None,
+        soulbound: false,
     });
 
     contract
@@ -671,6 +1409,7 @@ fn query_tokens_by_owner() {
         owner: demeter.clone(),
         token_uri: None,
         extension: None,
+        soulbound: false,
     });
     contract
         .execute(deps.as_mut(), mock_env(), minter.clone(), mint_msg)
@@ -681,6 +1420,7 @@ fn query_tokens_by_owner() {
         owner: ceres.clone(),
         token_uri: None,
         extension: None,
+        soulbound: false,
     });
     contract
         .execute(deps.as_mut(), mock_env(), minter.clone(), mint_msg)
@@ -691,6 +1431,7 @@ fn query_tokens_by_owner() {
         owner: demeter.clone(),
         token_uri: None,
         extension: None,
+        soulbound: false,
     });
     contract
         .execute(deps.as_mut(), mock_env(), minter, mint_msg)
@@ -729,3 +1470,366 @@ fn query_tokens_by_owner() {
         .unwrap();
     assert_eq!(&by_demeter[1..], &tokens.tokens[..]);
 }
+
+#[test]
+fn query_tokens_with_info() {
+    let mut deps = mock_dependencies();
+    let contract = setup_contract(deps.as_mut());
+    let minter = mock_info(MINTER, &[]);
+
+    for (token_id, owner) in [
+        ("grow1", "demeter"),
+        ("grow2", "ceres"),
+        ("sing", "demeter"),
+    ] {
+        let mint_msg = ExecuteMsg::Mint(MintMsg::<Extension> {
+            token_id: token_id.to_string(),
+            owner: owner.to_string(),
+            token_uri: Some(format!("https://example.com/{}", token_id)),
+            extension: None,
+            soulbound: token_id == "sing",
+        });
+        contract
+            .execute(deps.as_mut(), mock_env(), minter.clone(), mint_msg)
+            .unwrap();
+    }
+    let token_ids = |res: TokensWithInfoResponse<Extension>| -> Vec<String> {
+        res.tokens.into_iter().map(|t| t.token_id).collect()
+    };
+
+    // batch lookup keeps the requested order
+    let res = contract
+        .nft_infos(deps.as_ref(), vec!["sing".to_string(), "grow2".to_string()])
+        .unwrap();
+    assert_eq!(
+        res.tokens[0],
+        TokenWithInfo {
+            token_id: "sing".to_string(),
+            owner: "demeter".to_string(),
+            info: crate::msg::NftInfoResponse {
+                token_uri: Some("https://example.com/sing".to_string()),
+                extension: None,
+                soulbound: true,
+            },
+        }
+    );
+    assert_eq!(res.tokens[1].owner, "ceres");
+    contract
+        .nft_infos(
+            deps.as_ref(),
+            vec!["grow1".to_string(), "unknown".to_string()],
+        )
+        .unwrap_err();
+    contract
+        .nft_infos(deps.as_ref(), vec!["grow1".to_string(); 31])
+        .unwrap_err();
+
+    // all tokens, paginated
+    let res: TokensWithInfoResponse<Extension> = from_binary(
+        &contract
+            .query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::AllTokensWithInfo {
+                    start_after: None,
+                    limit: Some(2),
+                },
+            )
+            .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(token_ids(res), vec!["grow1", "grow2"]);
+    let res = contract
+        .all_tokens_with_info(deps.as_ref(), Some("grow2".to_string()), None)
+        .unwrap();
+    assert_eq!(token_ids(res), vec!["sing"]);
+
+    // by owner, paginated
+    let res = contract
+        .tokens_with_info(deps.as_ref(), "demeter".to_string(), None, Some(1))
+        .unwrap();
+    assert_eq!(token_ids(res), vec!["grow1"]);
+    let res = contract
+        .tokens_with_info(
+            deps.as_ref(),
+            "demeter".to_string(),
+            Some("grow1".to_string()),
+            Some(10),
+        )
+        .unwrap();
+    assert_eq!(token_ids(res), vec!["sing"]);
+    let res = contract
+        .tokens_with_info(deps.as_ref(), "ceres".to_string(), None, None)
+        .unwrap();
+    assert_eq!(res.tokens[0].owner, "ceres");
+    assert_eq!(token_ids(res), vec!["grow2"]);
+}
+
+#[test]
+fn trait_index() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: String::from(MINTER),
+        soulbound: false,
+        index_traits: true,
+    };
+    contract
+        .instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg)
+        .unwrap();
+    let minter = mock_info(MINTER, &[]);
+
+    let metadata = |traits: &[(&str, &str)]| {
+        Some(Metadata {
+            attributes: Some(
+                traits
+                    .iter()
+                    .map(|(trait_type, value)| Trait {
+                        display_type: None,
+                        trait_type: trait_type.to_string(),
+                        value: value.to_string(),
+                    })
+                    .collect(),
+            ),
+            ..Metadata::default()
+        })
+    };
+    for (token_id, extension) in [
+        ("1", metadata(&[("hat", "red"), ("eyes", "blue")])),
+        // repeated attributes count once
+        ("2", metadata(&[("hat", "red"), ("hat", "red")])),
+        ("3", metadata(&[("hat", "blue")])),
+        ("4", None),
+    ] {
+        let mint_msg = ExecuteMsg::Mint(MintMsg::<Extension> {
+            token_id: token_id.to_string(),
+            owner: String::from("venus"),
+            token_uri: None,
+            extension,
+            soulbound: false,
+        });
+        contract
+            .execute(deps.as_mut(), mock_env(), minter.clone(), mint_msg)
+            .unwrap();
+    }
+
+    let tokens_by_trait = |deps: &cosmwasm_std::OwnedDeps<_, _, _>, value: &str| {
+        let res: TokensResponse = from_binary(
+            &contract
+                .query(
+                    deps.as_ref(),
+                    mock_env(),
+                    QueryMsg::TokensByTrait {
+                        trait_type: "hat".to_string(),
+                        value: value.to_string(),
+                        start_after: None,
+                        limit: None,
+                    },
+                )
+                .unwrap(),
+        )
+        .unwrap();
+        res.tokens
+    };
+    let count = |trait_type: &str, value: &str, count: u64| TraitCount {
+        trait_type: trait_type.to_string(),
+        value: value.to_string(),
+        count,
+    };
+    assert_eq!(tokens_by_trait(&deps, "red"), vec!["1", "2"]);
+    let res = contract
+        .tokens_by_trait(
+            deps.as_ref(),
+            "hat".to_string(),
+            "red".to_string(),
+            Some("1".to_string()),
+            Some(1),
+        )
+        .unwrap();
+    assert_eq!(res.tokens, vec!["2"]);
+    assert_eq!(
+        contract
+            .trait_counts(deps.as_ref(), None, None)
+            .unwrap()
+            .counts,
+        // ordered by the length prefixed storage keys
+        vec![
+            count("hat", "blue", 1),
+            count("hat", "red", 2),
+            count("eyes", "blue", 1)
+        ]
+    );
+    let res = contract
+        .trait_counts(
+            deps.as_ref(),
+            Some(("hat".to_string(), "blue".to_string())),
+            Some(1),
+        )
+        .unwrap();
+    assert_eq!(res.counts, vec![count("hat", "red", 2)]);
+
+    // edits move the token between traits
+    let edit_msg = ExecuteMsg::Edit(EditMsg::<Extension> {
+        token_id: "2".to_string(),
+        token_uri: None,
+        extension: metadata(&[("hat", "blue")]),
+    });
+    contract
+        .execute(deps.as_mut(), mock_env(), minter, edit_msg)
+        .unwrap();
+    assert_eq!(tokens_by_trait(&deps, "red"), vec!["1"]);
+    assert_eq!(tokens_by_trait(&deps, "blue"), vec!["2", "3"]);
+
+    // burning removes the token and drops empty counts
+    let burn_msg = ExecuteMsg::Burn {
+        token_id: "1".to_string(),
+    };
+    contract
+        .execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), burn_msg)
+        .unwrap();
+    assert!(tokens_by_trait(&deps, "red").is_empty());
+    assert_eq!(
+        contract
+            .trait_counts(deps.as_ref(), None, None)
+            .unwrap()
+            .counts,
+        vec![count("hat", "blue", 2)]
+    );
+
+    // nothing is indexed unless enabled
+    let mut deps = mock_dependencies();
+    let contract = setup_contract(deps.as_mut());
+    let mint_msg = ExecuteMsg::Mint(MintMsg::<Extension> {
+        token_id: "1".to_string(),
+        owner: String::from("venus"),
+        token_uri: None,
+        extension: metadata(&[("hat", "red")]),
+        soulbound: false,
+    });
+    contract
+        .execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg)
+        .unwrap();
+    assert!(contract
+        .trait_counts(deps.as_ref(), None, None)
+        .unwrap()
+        .counts
+        .is_empty());
+}
+
+#[test]
+fn batch_transfer_send_and_burn() {
+    let mut deps = mock_dependencies();
+    let contract = setup_contract(deps.as_mut());
+    let minter = mock_info(MINTER, &[]);
+    let venus = mock_info("venus", &[]);
+
+    for token_id in ["1", "2", "3", "4"] {
+        let mint_msg = ExecuteMsg::Mint(MintMsg::<Extension> {
+            token_id: token_id.to_string(),
+            owner: String::from("venus"),
+            token_uri: None,
+            extension: None,
+            soulbound: false,
+        });
+        contract
+            .execute(deps.as_mut(), mock_env(), minter.clone(), mint_msg)
+            .unwrap();
+    }
+    let ids =
+        |token_ids: &[&str]| -> Vec<String> { token_ids.iter().map(|id| id.to_string()).collect() };
+    let owner = |deps: &cosmwasm_std::OwnedDeps<_, _, _>, token_id: &str| {
+        contract
+            .owner_of(deps.as_ref(), mock_env(), token_id.to_string(), false)
+            .unwrap()
+            .owner
+    };
+
+    // batches are bounded
+    for token_ids in [vec![], vec!["1".to_string(); 31]] {
+        let err = contract
+            .execute(
+                deps.as_mut(),
+                mock_env(),
+                venus.clone(),
+                ExecuteMsg::BatchBurn { token_ids },
+            )
+            .unwrap_err();
+        assert_eq!(err, ContractError::InvalidBatchSize {});
+    }
+
+    // transfer
+    let transfer = |token_ids: Vec<String>| ExecuteMsg::BatchTransferNft {
+        recipient: String::from("random"),
+        token_ids,
+    };
+    let err = contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("random", &[]),
+            transfer(ids(&["1", "2"])),
+        )
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            venus.clone(),
+            transfer(ids(&["1", "2"])),
+        )
+        .unwrap();
+    assert_eq!(owner(&deps, "1"), "random");
+    assert_eq!(owner(&deps, "2"), "random");
+    assert_eq!(owner(&deps, "3"), "venus");
+
+    // send, with one callback per token
+    let msg = to_binary("Batch").unwrap();
+    let res = contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            venus.clone(),
+            ExecuteMsg::BatchSendNft {
+                contract: String::from("another_contract"),
+                token_ids: ids(&["3", "4"]),
+                msg: msg.clone(),
+            },
+        )
+        .unwrap();
+    let expected: Vec<_> = ["3", "4"]
+        .iter()
+        .map(|token_id| {
+            Cw721ReceiveMsg {
+                sender: String::from("venus"),
+                token_id: token_id.to_string(),
+                msg: msg.clone(),
+            }
+            .into_cosmos_msg("another_contract")
+            .unwrap()
+        })
+        .collect();
+    assert_eq!(
+        res.messages.into_iter().map(|m| m.msg).collect::<Vec<_>>(),
+        expected
+    );
+    assert_eq!(owner(&deps, "4"), "another_contract");
+
+    // burn
+    contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("random", &[]),
+            ExecuteMsg::BatchBurn {
+                token_ids: ids(&["1", "2"]),
+            },
+        )
+        .unwrap();
+    assert_eq!(contract.num_tokens(deps.as_ref()).unwrap().count, 2);
+    contract
+        .nft_info(deps.as_ref(), "1".to_string())
+        .unwrap_err();
+}
@@ -12,12 +12,30 @@ pub enum ContractError {
     #[error("BatchMint count not match")]
     CountNotMatch {},
 
+    #[error("Batch must hold between 1 and 30 tokens")]
+    InvalidBatchSize {},
+
     #[error("token_id already claimed")]
     Claimed {},
 
+    #[error("Token metadata is frozen")]
+    Frozen {},
+
+    #[error("Metadata is permanently frozen")]
+    MetadataFrozen {},
+
+    #[error("Metadata freeze is still in progress")]
+    FreezeInProgress {},
+
     #[error("Cannot set approval that is already expired")]
     Expired {},
 
+    #[error("Transfer not allowed by the transfer policy")]
+    TransferRestricted {},
+
+    #[error("Token is soulbound")]
+    Soulbound {},
+
     #[error("Approval not found for: {spender}")]
     ApprovalNotFound { spender: String },
 }
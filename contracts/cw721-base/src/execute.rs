@@ -1,20 +1,32 @@
 use serde::de::DeserializeOwned;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 
-use cosmwasm_std::{Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Addr};
+use cosmwasm_std::{
+    from_slice, to_binary, to_vec, Binary, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Response,
+    StdError, StdResult, Addr, Order, Storage, WasmMsg,
+};
+use cw_storage_plus::Bound;
+use sha2::Digest;
 
 use cw2::set_contract_version;
 use cw721::{ContractInfoResponse, CustomMsg, Cw721Execute, Cw721ReceiveMsg, Expiration};
 
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, MintMsg, BatchMintMsg, EditMsg};
-use crate::state::{Approval, Cw721Contract, TokenInfo};
+use crate::msg::{
+    ExecuteMsg, InstantiateMsg, MintMsg, BatchMintMsg, EditMsg, TransferHookMsg, TransferPolicy,
+    TransferRule,
+};
+use crate::state::{Approval, Cw721Contract, FreezeProgress, TokenInfo};
+use crate::Trait;
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:cw721-base";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 const ONLYOWNER:bool = false;
+const MAX_BATCH_SIZE: usize = 30;
+const DEFAULT_FREEZE_LIMIT: u32 = 100;
+const MAX_FREEZE_LIMIT: u32 = 500;
 
 impl<'a, T, C> Cw721Contract<'a, T, C>
 where
@@ -37,6 +49,12 @@ where
         self.contract_info.save(deps.storage, &info)?;
         let minter = deps.api.addr_validate(&msg.minter)?;
         self.minter.save(deps.storage, &minter)?;
+        if msg.soulbound {
+            self.soulbound.save(deps.storage, &true)?;
+        }
+        if msg.index_traits {
+            self.index_traits.save(deps.storage, &true)?;
+        }
         Ok(Response::default())
     }
 
@@ -51,7 +69,14 @@ where
             ExecuteMsg::ChangeMinter{new_minter} => {
                 self.change_minter(deps, info, new_minter)
             },
-            // ExecuteMsg::Edit(msg) => self.edit(deps, env, info, msg),
+            ExecuteMsg::SetTransferPolicy { policy } => {
+                self.set_transfer_policy(deps, info, policy)
+            }
+            ExecuteMsg::Edit(msg) => self.edit(deps, env, info, msg),
+            ExecuteMsg::SetFrozen { token_id, frozen } => {
+                self.set_frozen(deps, info, token_id, frozen)
+            }
+            ExecuteMsg::FreezeMetadata { limit } => self.freeze_metadata(deps, info, limit),
             ExecuteMsg::Mint(msg) => self.mint(deps, env, info, msg),
             ExecuteMsg::BatchMint(msg) => self.batch_mint(deps, env, info, msg),
             ExecuteMsg::Approve {
@@ -76,6 +101,16 @@ where
                 msg,
             } => self.send_nft(deps, env, info, contract, token_id, msg),
             ExecuteMsg::Burn { token_id } => self.burn(deps, env, info, token_id),
+            ExecuteMsg::BatchTransferNft {
+                recipient,
+                token_ids,
+            } => self.batch_transfer_nft(deps, env, info, recipient, token_ids),
+            ExecuteMsg::BatchSendNft {
+                contract,
+                token_ids,
+                msg,
+            } => self.batch_send_nft(deps, env, info, contract, token_ids, msg),
+            ExecuteMsg::BatchBurn { token_ids } => self.batch_burn(deps, env, info, token_ids),
         }
     }
 }
@@ -106,33 +141,168 @@ where
 
     }
 
-    
-    // pub fn edit(
-    //     &self,
-    //     deps: DepsMut,
-    //     _env: Env,
-    //     info: MessageInfo,
-    //     msg: EditMsg<T>,
-    // ) -> Result<Response<C>, ContractError> {
-        
-    //     let mut minter = self.minter.load(deps.storage)?;
-        
-    //     if info.sender != minter {
-    //         return Err(ContractError::Unauthorized {});
-    //     }
-    //     let mut token = self.tokens.load(deps.storage, &msg.token_id)?;
-        
-    //     token.extension = msg.extension;
-    //     token.token_uri = msg.token_uri;
+    pub fn set_transfer_policy(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        policy: Option<TransferPolicy>,
+    ) -> Result<Response<C>, ContractError> {
+        let minter = self.minter.load(deps.storage)?;
+
+        if info.sender != minter {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        let rule = match &policy {
+            Some(policy) => {
+                self.transfer_policy.save(deps.storage, policy)?;
+                match policy.rule {
+                    TransferRule::Operators { .. } => "operators",
+                    TransferRule::Hook { .. } => "hook",
+                }
+            }
+            None => {
+                self.transfer_policy.remove(deps.storage);
+                "none"
+            }
+        };
+
+        Ok(Response::new()
+            .add_attribute("action", "set_transfer_policy")
+            .add_attribute("rule", rule))
+    }
+
+    pub fn edit(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        msg: EditMsg<T>,
+    ) -> Result<Response<C>, ContractError> {
+        let minter = self.minter.load(deps.storage)?;
+
+        if info.sender != minter {
+            return Err(ContractError::Unauthorized {});
+        }
+        if self.is_frozen(deps.storage, &msg.token_id)? {
+            return Err(ContractError::Frozen {});
+        }
+        let mut token = self.tokens.load(deps.storage, &msg.token_id)?;
+
+        self.update_trait_index(deps.storage, &msg.token_id, &token.extension, false)?;
+        self.update_trait_index(deps.storage, &msg.token_id, &msg.extension, true)?;
+        token.extension = msg.extension;
+        token.token_uri = msg.token_uri;
+
+        self.tokens.save(deps.storage, &msg.token_id, &token)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "edit")
+            .add_attribute("editor", info.sender)
+            .add_attribute("token_id", msg.token_id))
+    }
+
+    pub fn set_frozen(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        token_id: Option<String>,
+        frozen: bool,
+    ) -> Result<Response<C>, ContractError> {
+        let minter = self.minter.load(deps.storage)?;
 
-    //     self.tokens.save(deps.storage, &msg.token_id, &token)?;
+        if info.sender != minter {
+            return Err(ContractError::Unauthorized {});
+        }
 
+        match token_id.clone() {
+            Some(token_id) => {
+                // make sure the token exists
+                self.tokens.load(deps.storage, &token_id)?;
+                if frozen {
+                    self.frozen_tokens.save(deps.storage, &token_id, &true)?;
+                } else {
+                    self.frozen_tokens.remove(deps.storage, &token_id);
+                }
+            }
+            None => self.frozen.save(deps.storage, &frozen)?,
+        }
 
-    //     Ok(Response::new()
-    //         .add_attribute("action", "edit")
-    //         .add_attribute("editor", info.sender.clone())
-    //         .add_attribute("token_id", msg.token_id))
-    // }
+        Ok(Response::new()
+            .add_attribute("action", "set_frozen")
+            .add_attribute("token_id", token_id.unwrap_or_else(|| "all".to_string()))
+            .add_attribute("frozen", frozen.to_string()))
+    }
+
+    pub fn freeze_metadata(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        limit: Option<u32>,
+    ) -> Result<Response<C>, ContractError> {
+        let minter = self.minter.load(deps.storage)?;
+
+        if info.sender != minter {
+            return Err(ContractError::Unauthorized {});
+        }
+        if self.provenance.may_load(deps.storage)?.is_some() {
+            return Err(ContractError::MetadataFrozen {});
+        }
+
+        // the hash is chained per token, so it does not depend on how the calls were split
+        let progress = self.freeze_progress.may_load(deps.storage)?;
+        let mut hash = match &progress {
+            Some(progress) => hex::decode(&progress.hash)
+                .map_err(|_| StdError::generic_err("Invalid freeze progress hash"))?,
+            None => vec![],
+        };
+        let start = progress.map(|progress| Bound::exclusive(progress.last_token_id));
+        let limit = limit.unwrap_or(DEFAULT_FREEZE_LIMIT).clamp(1, MAX_FREEZE_LIMIT) as usize;
+
+        let mut last_token_id = None;
+        let mut hashed = 0;
+        for item in self
+            .tokens
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+        {
+            let (token_id, token) = item?;
+            let mut hasher = sha2::Sha256::new();
+            hasher.update(&hash);
+            hasher.update(&token_id);
+            hasher.update(b":");
+            hasher.update(token.token_uri.unwrap_or_default());
+            hasher.update(b"\n");
+            hash = hasher.finalize().to_vec();
+            last_token_id = Some(token_id);
+            hashed += 1;
+        }
+
+        let res = Response::new()
+            .add_attribute("action", "freeze_metadata")
+            .add_attribute("hashed", hashed.to_string());
+        if hashed == limit {
+            self.freeze_progress.save(
+                deps.storage,
+                &FreezeProgress {
+                    last_token_id: last_token_id.unwrap_or_default(),
+                    hash: hex::encode(&hash),
+                },
+            )?;
+            return Ok(res.add_attribute("complete", "false"));
+        }
+
+        if hash.is_empty() {
+            hash = sha2::Sha256::digest(b"").to_vec();
+        }
+        let provenance_hash = hex::encode(hash);
+        self.freeze_progress.remove(deps.storage);
+        self.provenance.save(deps.storage, &provenance_hash)?;
+
+        Ok(res
+            .add_attribute("complete", "true")
+            .add_attribute("provenance_hash", provenance_hash))
+    }
 
     pub fn mint(
         &self,
@@ -146,7 +316,11 @@ where
         if info.sender != minter {
             return Err(ContractError::Unauthorized {});
         }
+        if self.is_metadata_frozen(deps.storage)? {
+            return Err(ContractError::MetadataFrozen {});
+        }
 
+        self.update_trait_index(deps.storage, &msg.token_id, &msg.extension, true)?;
         // create the token
         let token = TokenInfo {
             owner: deps.api.addr_validate(&msg.owner)?,
@@ -159,13 +333,17 @@ where
                 Some(_) => Err(ContractError::Claimed {}),
                 None => Ok(token),
             })?;
+        if msg.soulbound {
+            self.soulbound_tokens.save(deps.storage, &msg.token_id, &true)?;
+        }
 
         self.increment_tokens(deps.storage)?;
 
         Ok(Response::new()
             .add_attribute("action", "mint")
             .add_attribute("minter", info.sender)
-            .add_attribute("token_id", msg.token_id))
+            .add_attribute("token_id", msg.token_id)
+            .add_attribute("soulbound", msg.soulbound.to_string()))
     }
 
     pub fn batch_mint(
@@ -183,8 +361,12 @@ where
         if msg.token_id.len() != msg.token_uri.len() {
             return Err(ContractError::CountNotMatch {});
         }
+        if self.is_metadata_frozen(deps.storage)? {
+            return Err(ContractError::MetadataFrozen {});
+        }
 
         for i in 0..msg.token_id.len() {
+            self.update_trait_index(deps.storage, &msg.token_id[i], &msg.extension[i], true)?;
             // create the token
             let token = TokenInfo {
                 owner: deps.api.addr_validate(&msg.owner[i].clone())?,
@@ -207,6 +389,104 @@ where
             .add_attribute("count", msg.token_uri.clone().len().to_string()))
     }
 
+    pub fn batch_transfer_nft(
+        &self,
+        mut deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        recipient: String,
+        token_ids: Vec<String>,
+    ) -> Result<Response<C>, ContractError> {
+        if ONLYOWNER {
+            let minter = self.minter.load(deps.storage)?;
+
+            if info.sender != minter {
+                return Err(ContractError::Unauthorized {});
+            }
+        }
+        check_batch_size(&token_ids)?;
+
+        let mut hooks = vec![];
+        for token_id in &token_ids {
+            hooks.extend(self.check_transfer_policy(deps.as_ref(), &info, &recipient, token_id)?);
+            self._transfer_nft(deps.branch(), &env, &info, &recipient, token_id)?;
+        }
+
+        Ok(Response::new()
+            .add_messages(hooks)
+            .add_attribute("action", "batch_transfer_nft")
+            .add_attribute("sender", info.sender)
+            .add_attribute("recipient", recipient)
+            .add_attribute("token_ids", token_ids.join(",")))
+    }
+
+    pub fn batch_send_nft(
+        &self,
+        mut deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        contract: String,
+        token_ids: Vec<String>,
+        msg: Binary,
+    ) -> Result<Response<C>, ContractError> {
+        if ONLYOWNER {
+            let minter = self.minter.load(deps.storage)?;
+
+            if info.sender != minter {
+                return Err(ContractError::Unauthorized {});
+            }
+        }
+        check_batch_size(&token_ids)?;
+
+        let mut hooks = vec![];
+        let mut sends = vec![];
+        for token_id in &token_ids {
+            hooks.extend(self.check_transfer_policy(deps.as_ref(), &info, &contract, token_id)?);
+            self._transfer_nft(deps.branch(), &env, &info, &contract, token_id)?;
+
+            let send = Cw721ReceiveMsg {
+                sender: info.sender.to_string(),
+                token_id: token_id.clone(),
+                msg: msg.clone(),
+            };
+            sends.push(send.into_cosmos_msg(contract.clone())?);
+        }
+
+        Ok(Response::new()
+            .add_messages(hooks)
+            .add_messages(sends)
+            .add_attribute("action", "batch_send_nft")
+            .add_attribute("sender", info.sender)
+            .add_attribute("recipient", contract)
+            .add_attribute("token_ids", token_ids.join(",")))
+    }
+
+    pub fn batch_burn(
+        &self,
+        mut deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_ids: Vec<String>,
+    ) -> Result<Response<C>, ContractError> {
+        if ONLYOWNER {
+            let minter = self.minter.load(deps.storage)?;
+
+            if info.sender != minter {
+                return Err(ContractError::Unauthorized {});
+            }
+        }
+        check_batch_size(&token_ids)?;
+
+        for token_id in &token_ids {
+            self._burn_nft(deps.branch(), &env, &info, token_id)?;
+        }
+
+        Ok(Response::new()
+            .add_attribute("action", "batch_burn")
+            .add_attribute("sender", info.sender)
+            .add_attribute("token_ids", token_ids.join(",")))
+    }
+
 }
 
 impl<'a, T, C> Cw721Execute<T, C> for Cw721Contract<'a, T, C>
@@ -232,9 +512,11 @@ where
                 return Err(ContractError::Unauthorized {});
             }
         }
+        let hook = self.check_transfer_policy(deps.as_ref(), &info, &recipient, &token_id)?;
         self._transfer_nft(deps, &env, &info, &recipient, &token_id)?;
 
         Ok(Response::new()
+            .add_messages(hook)
             .add_attribute("action", "transfer_nft")
             .add_attribute("sender", info.sender)
             .add_attribute("recipient", recipient)
@@ -258,6 +540,7 @@ where
             }
         }
         // Transfer token
+        let hook = self.check_transfer_policy(deps.as_ref(), &info, &contract, &token_id)?;
         self._transfer_nft(deps, &env, &info, &contract, &token_id)?;
 
         let send = Cw721ReceiveMsg {
@@ -268,6 +551,7 @@ where
 
         // Send message
         Ok(Response::new()
+            .add_messages(hook)
             .add_message(send.into_cosmos_msg(contract.clone())?)
             .add_attribute("action", "send_nft")
             .add_attribute("sender", info.sender)
@@ -339,6 +623,10 @@ where
                 return Err(ContractError::Unauthorized {});
             }
         }
+        // operators can not do anything with non-transferable tokens
+        if self.soulbound.may_load(deps.storage)?.unwrap_or_default() {
+            return Err(ContractError::Soulbound {});
+        }
         // reject expired data as invalid
         let expires = expires.unwrap_or_default();
         if expires.is_expired(&env.block) {
@@ -394,11 +682,7 @@ where
                 return Err(ContractError::Unauthorized {});
             }
         }
-        let token = self.tokens.load(deps.storage, &token_id)?;
-        self.check_can_send(deps.as_ref(), &env, &info, &token)?;
-
-        self.tokens.remove(deps.storage, &token_id)?;
-        self.decrement_tokens(deps.storage)?;
+        self._burn_nft(deps, &env, &info, &token_id)?;
 
         Ok(Response::new()
             .add_attribute("action", "burn")
@@ -413,6 +697,62 @@ where
     T: Serialize + DeserializeOwned + Clone,
     C: CustomMsg,
 {
+    /// Adds or removes the token under each attribute of its extension, if traits are indexed
+    pub fn update_trait_index(
+        &self,
+        storage: &mut dyn Storage,
+        token_id: &str,
+        extension: &T,
+        add: bool,
+    ) -> StdResult<()> {
+        // checked first so contracts without the index never serialize extensions
+        if !self.index_traits.may_load(storage)?.unwrap_or_default() {
+            return Ok(());
+        }
+        for attribute in extension_traits(extension) {
+            let key = (attribute.trait_type.as_str(), attribute.value.as_str(), token_id);
+            // attributes may repeat within one token, count it once
+            if self.trait_tokens.has(storage, key) == add {
+                continue;
+            }
+            let count_key = (key.0, key.1);
+            let count = self.trait_counts.may_load(storage, count_key)?.unwrap_or_default();
+            if add {
+                self.trait_tokens.save(storage, key, &true)?;
+                self.trait_counts.save(storage, count_key, &(count + 1))?;
+            } else {
+                self.trait_tokens.remove(storage, key);
+                if count > 1 {
+                    self.trait_counts.save(storage, count_key, &(count - 1))?;
+                } else {
+                    self.trait_counts.remove(storage, count_key);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn _burn_nft(
+        &self,
+        deps: DepsMut,
+        env: &Env,
+        info: &MessageInfo,
+        token_id: &str,
+    ) -> Result<(), ContractError> {
+        // the freeze could not reproduce its hash if tokens disappeared in between
+        if self.freeze_progress.may_load(deps.storage)?.is_some() {
+            return Err(ContractError::FreezeInProgress {});
+        }
+        let token = self.tokens.load(deps.storage, token_id)?;
+        self.check_can_send(deps.as_ref(), env, info, &token)?;
+
+        self.tokens.remove(deps.storage, token_id)?;
+        self.soulbound_tokens.remove(deps.storage, token_id);
+        self.update_trait_index(deps.storage, token_id, &token.extension, false)?;
+        self.decrement_tokens(deps.storage)?;
+        Ok(())
+    }
+
     pub fn _transfer_nft(
         &self,
         deps: DepsMut,
@@ -422,6 +762,9 @@ where
         token_id: &str,
     ) -> Result<TokenInfo<T>, ContractError> {
         let mut token = self.tokens.load(deps.storage, token_id)?;
+        if self.is_soulbound(deps.storage, token_id)? {
+            return Err(ContractError::Soulbound {});
+        }
         // ensure we have permissions
         self.check_can_send(deps.as_ref(), env, info, &token)?;
         // set owner and remove existing approvals
@@ -431,6 +774,67 @@ where
         Ok(token)
     }
 
+    /// Applies the transfer policy, returning the hook call to run with the transfer if any
+    pub fn check_transfer_policy(
+        &self,
+        deps: Deps,
+        info: &MessageInfo,
+        recipient: &str,
+        token_id: &str,
+    ) -> Result<Option<CosmosMsg<C>>, ContractError> {
+        let policy = match self.transfer_policy.may_load(deps.storage)? {
+            Some(policy) => policy,
+            None => return Ok(None),
+        };
+        let token = self.tokens.load(deps.storage, token_id)?;
+        let gift = policy.allow_gifts
+            && token.owner == info.sender
+            && self.holds_tokens(deps, &deps.api.addr_validate(recipient)?)?;
+
+        match policy.rule {
+            TransferRule::Operators { operators } => {
+                let through_operator = operators
+                    .iter()
+                    .any(|operator| *operator == info.sender || operator.as_str() == recipient);
+                if !through_operator && !gift {
+                    return Err(ContractError::TransferRestricted {});
+                }
+                Ok(None)
+            }
+            TransferRule::Hook { contract } => {
+                if gift {
+                    return Ok(None);
+                }
+                let msg = TransferHookMsg::CheckTransfer {
+                    sender: info.sender.to_string(),
+                    owner: token.owner.to_string(),
+                    recipient: recipient.to_string(),
+                    token_id: token_id.to_string(),
+                };
+                Ok(Some(
+                    WasmMsg::Execute {
+                        contract_addr: contract.to_string(),
+                        msg: to_binary(&msg)?,
+                        funds: vec![],
+                    }
+                    .into(),
+                ))
+            }
+        }
+    }
+
+    // gifts only go to existing holders, so they can't hand tokens to fresh wallets
+    fn holds_tokens(&self, deps: Deps, address: &Addr) -> StdResult<bool> {
+        Ok(self
+            .tokens
+            .idx
+            .owner
+            .prefix(address.clone())
+            .keys_raw(deps.storage, None, None, Order::Ascending)
+            .next()
+            .is_some())
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub fn _update_approvals(
         &self,
@@ -457,6 +861,9 @@ where
 
         // only difference between approve and revoke
         if add {
+            if self.is_soulbound(deps.storage, token_id)? {
+                return Err(ContractError::Soulbound {});
+            }
             // reject expired data as invalid
             let expires = expires.unwrap_or_default();
             if expires.is_expired(&env.block) {
@@ -540,3 +947,24 @@ where
         }
     }
 }
+
+fn check_batch_size(token_ids: &[String]) -> Result<(), ContractError> {
+    if token_ids.is_empty() || token_ids.len() > MAX_BATCH_SIZE {
+        return Err(ContractError::InvalidBatchSize {});
+    }
+    Ok(())
+}
+
+/// The `attributes` of an extension shaped like `Metadata`, empty for any other extension
+fn extension_traits<T: Serialize>(extension: &T) -> Vec<Trait> {
+    #[derive(Deserialize)]
+    struct Attributes {
+        attributes: Option<Vec<Trait>>,
+    }
+
+    to_vec(extension)
+        .and_then(|data| from_slice::<Attributes>(&data))
+        .ok()
+        .and_then(|extension| extension.attributes)
+        .unwrap_or_default()
+}
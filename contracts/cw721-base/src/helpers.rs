@@ -1,3 +1,4 @@
+use crate::msg::TokensWithInfoResponse;
 use crate::{ExecuteMsg, QueryMsg};
 use cosmwasm_std::{to_binary, Addr, CosmosMsg, QuerierWrapper, StdResult, WasmMsg, WasmQuery};
 use cw721::{
@@ -167,6 +168,43 @@ impl Cw721Contract {
         self.query(querier, req)
     }
 
+    /// With metadata extension, at most 30 token ids at once
+    pub fn nft_infos<U: DeserializeOwned>(
+        &self,
+        querier: &QuerierWrapper,
+        token_ids: Vec<String>,
+    ) -> StdResult<TokensWithInfoResponse<U>> {
+        let req = QueryMsg::NftInfos { token_ids };
+        self.query(querier, req)
+    }
+
+    /// With enumerable and metadata extensions
+    pub fn tokens_with_info<T: Into<String>, U: DeserializeOwned>(
+        &self,
+        querier: &QuerierWrapper,
+        owner: T,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<TokensWithInfoResponse<U>> {
+        let req = QueryMsg::TokensWithInfo {
+            owner: owner.into(),
+            start_after,
+            limit,
+        };
+        self.query(querier, req)
+    }
+
+    /// With enumerable and metadata extensions
+    pub fn all_tokens_with_info<U: DeserializeOwned>(
+        &self,
+        querier: &QuerierWrapper,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<TokensWithInfoResponse<U>> {
+        let req = QueryMsg::AllTokensWithInfo { start_after, limit };
+        self.query(querier, req)
+    }
+
     /// returns true if the contract supports the metadata extension
     pub fn has_metadata(&self, querier: &QuerierWrapper) -> bool {
         self.contract_info(querier).is_ok()
@@ -8,7 +8,11 @@ pub mod state;
 
 use schemars::JsonSchema;
 pub use crate::error::ContractError;
-pub use crate::msg::{ExecuteMsg, InstantiateMsg, MintMsg, MinterResponse, QueryMsg};
+pub use crate::msg::{
+    EditMsg, ExecuteMsg, FrozenResponse, InstantiateMsg, MintMsg, MinterResponse,
+    ProvenanceResponse, QueryMsg, TransferHookMsg, TransferPolicy, TransferPolicyResponse,
+    TransferRule,
+};
 pub use crate::state::Cw721Contract;
 use cosmwasm_std::Empty;
 use serde::{Deserialize, Serialize};
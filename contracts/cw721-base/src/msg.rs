@@ -16,6 +16,14 @@ pub struct InstantiateMsg {
     /// This is designed for a base NFT that is controlled by an external program
     /// or contract. You will likely replace this with custom logic in custom NFTs
     pub minter: String,
+
+    /// Makes every token non-transferable, they can only be minted and burned
+    #[serde(default)]
+    pub soulbound: bool,
+
+    /// Indexes the `attributes` of the token extension, for `TokensByTrait` and `TraitCounts`
+    #[serde(default)]
+    pub index_traits: bool,
 }
 
 /// This is like Cw721ExecuteMsg but we add a Mint command for an owner
@@ -53,14 +61,46 @@ pub enum ExecuteMsg<T> {
 
     /// Mint a new NFT, can only be called by the contract minter
     Mint(MintMsg<T>),
-    // Edit(EditMsg<T>),
+    /// Replace the uri and extension of an existing NFT, can only be called by the contract minter
+    Edit(EditMsg<T>),
+    /// Block or allow `Edit` for one token, or for every token if token_id is unset.
+    /// Can only be called by the contract minter
+    SetFrozen {
+        token_id: Option<String>,
+        frozen: bool,
+    },
+    /// Permanently freezes the metadata: from the first call on `Edit`, `Mint` and `BatchMint`
+    /// are refused for good, so no token can be added or changed anymore. Each call hashes
+    /// the uris of up to `limit` more tokens (default 100, max 500) and `Burn` is refused
+    /// until the call that hashes the last token stores the provenance hash.
+    /// Can only be called by the contract minter
+    FreezeMetadata { limit: Option<u32> },
     BatchMint(BatchMintMsg<T>),
     ChangeMinter {
         new_minter: Addr
     },
+    /// Restricts who can transfer and send tokens, lifted again when unset.
+    /// Can only be called by the contract minter
+    SetTransferPolicy {
+        policy: Option<TransferPolicy>,
+    },
 
     /// Burn an NFT the sender has access to
     Burn { token_id: String },
+    /// `TransferNft` for up to 30 tokens at once, fails as a whole if any transfer fails
+    BatchTransferNft {
+        recipient: String,
+        token_ids: Vec<String>,
+    },
+    /// `SendNft` for up to 30 tokens at once, the contract receives one `Cw721ReceiveMsg`
+    /// with the same msg per token
+    BatchSendNft {
+        contract: String,
+        token_ids: Vec<String>,
+        msg: Binary,
+    },
+    /// `Burn` for up to 30 tokens at once, fails as a whole if any burn fails
+    BatchBurn { token_ids: Vec<String> },
 }
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct EditMsg<T> {
@@ -83,6 +123,9 @@ pub struct MintMsg<T> {
     pub token_uri: Option<String>,
     /// Any custom extension used by this contract
     pub extension: T,
+    /// Makes this token non-transferable, it can only be burned by its owner
+    #[serde(default)]
+    pub soulbound: bool,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -142,7 +185,7 @@ pub enum QueryMsg {
     ContractInfo {},
     /// With MetaData Extension.
     /// Returns metadata about one particular token, based on *ERC721 Metadata JSON Schema*
-    /// but directly from the contract: `NftInfoResponse`
+    /// but directly from the contract: `NftInfoResponse`, including the soulbound flag
     NftInfo {
         token_id: String,
     },
@@ -171,8 +214,139 @@ pub enum QueryMsg {
         limit: Option<u32>,
     },
 
+    /// Owner and metadata of each of the given tokens, at most 30 at once.
+    /// Return type: TokensWithInfoResponse
+    NftInfos { token_ids: Vec<String> },
+    /// With Enumerable extension.
+    /// Like `Tokens`, but returns the owner and metadata of each token.
+    /// Return type: TokensWithInfoResponse
+    TokensWithInfo {
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// With Enumerable extension.
+    /// Like `AllTokens`, but returns the owner and metadata of each token.
+    /// Return type: TokensWithInfoResponse
+    AllTokensWithInfo {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
     // Return the minter
     Minter {},
+
+    /// Whether the metadata of the given token, or of the whole contract if unset, can be edited.
+    /// Return type: FrozenResponse
+    Frozen { token_id: Option<String> },
+
+    /// Whether the metadata was permanently frozen by `FreezeMetadata`.
+    /// Return type: FrozenResponse
+    MetadataFrozen {},
+
+    /// Hash of all token uris taken when the metadata was frozen.
+    /// Return type: ProvenanceResponse
+    Provenance {},
+
+    /// Restrictions on transfers, unset if tokens move freely.
+    /// Return type: TransferPolicyResponse
+    TransferPolicy {},
+
+    /// Tokens having the given attribute, requires `index_traits`.
+    /// Return type: TokensResponse
+    TokensByTrait {
+        trait_type: String,
+        value: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Number of tokens having each attribute, requires `index_traits`.
+    /// `start_after` is the (trait_type, value) of the last count of the previous page.
+    /// Return type: TraitCountsResponse
+    TraitCounts {
+        start_after: Option<(String, String)>,
+        limit: Option<u32>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum TransferRule {
+    /// Tokens only move through these operators (marketplaces), either sent to them
+    /// or transferred by them
+    Operators { operators: Vec<Addr> },
+    /// Every transfer is also executed on the hook contract as `TransferHookMsg`,
+    /// and an error there rejects it
+    Hook { contract: Addr },
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct TransferPolicy {
+    pub rule: TransferRule,
+    /// Lets owners transfer their own tokens directly, skipping the rule, to addresses that
+    /// already hold a token of this contract. Any holder qualifies, not only known ones
+    pub allow_gifts: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct TransferPolicyResponse {
+    pub policy: Option<TransferPolicy>,
+}
+
+/// Sent to the hook contract of a `TransferRule::Hook` policy before a transfer completes
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum TransferHookMsg {
+    CheckTransfer {
+        sender: String,
+        owner: String,
+        recipient: String,
+        token_id: String,
+    },
+}
+
+/// `cw721::NftInfoResponse` extended with the soulbound flag of the token
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct NftInfoResponse<T> {
+    pub token_uri: Option<String>,
+    pub extension: T,
+    pub soulbound: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct TokenWithInfo<T> {
+    pub token_id: String,
+    pub owner: String,
+    pub info: NftInfoResponse<T>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct TokensWithInfoResponse<T> {
+    pub tokens: Vec<TokenWithInfo<T>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct TraitCount {
+    pub trait_type: String,
+    pub value: String,
+    pub count: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct TraitCountsResponse {
+    pub counts: Vec<TraitCount>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct FrozenResponse {
+    pub frozen: bool,
+}
+
+/// Hex encoded sha256 chain over every token in token_id order, where each step hashes the
+/// previous digest followed by "{token_id}:{token_uri}\n", starting from no digest
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ProvenanceResponse {
+    pub provenance_hash: Option<String>,
 }
 
 /// Shows who can mint these tokens
@@ -8,10 +8,13 @@ use cw721::{
     Cw721Query, Expiration, NftInfoResponse, NumTokensResponse, OperatorsResponse, OwnerOfResponse,
     TokensResponse,
 };
-use cw_storage_plus::Bound;
+use cw_storage_plus::{Bound, PrimaryKey};
 use cw_utils::maybe_addr;
 
-use crate::msg::{MinterResponse, QueryMsg};
+use crate::msg::{
+    self, FrozenResponse, MinterResponse, ProvenanceResponse, QueryMsg, TokenWithInfo,
+    TokensWithInfoResponse, TraitCount, TraitCountsResponse, TransferPolicyResponse,
+};
 use crate::state::{Approval, Cw721Contract, TokenInfo};
 
 const DEFAULT_LIMIT: u32 = 10;
@@ -208,11 +211,201 @@ where
         })
     }
 
+    pub fn frozen(&self, deps: Deps, token_id: Option<String>) -> StdResult<FrozenResponse> {
+        let frozen = match token_id {
+            Some(token_id) => self.is_frozen(deps.storage, &token_id)?,
+            None => {
+                self.is_metadata_frozen(deps.storage)?
+                    || self.frozen.may_load(deps.storage)?.unwrap_or_default()
+            }
+        };
+        Ok(FrozenResponse { frozen })
+    }
+
+    pub fn provenance(&self, deps: Deps) -> StdResult<ProvenanceResponse> {
+        Ok(ProvenanceResponse {
+            provenance_hash: self.provenance.may_load(deps.storage)?,
+        })
+    }
+
+    pub fn nft_info_with_soulbound(
+        &self,
+        deps: Deps,
+        token_id: String,
+    ) -> StdResult<msg::NftInfoResponse<T>> {
+        let info = self.tokens.load(deps.storage, &token_id)?;
+        Ok(msg::NftInfoResponse {
+            token_uri: info.token_uri,
+            extension: info.extension,
+            soulbound: self.is_soulbound(deps.storage, &token_id)?,
+        })
+    }
+
+    fn token_with_info(
+        &self,
+        deps: Deps,
+        token_id: String,
+        info: TokenInfo<T>,
+    ) -> StdResult<TokenWithInfo<T>> {
+        Ok(TokenWithInfo {
+            owner: info.owner.to_string(),
+            info: msg::NftInfoResponse {
+                token_uri: info.token_uri,
+                extension: info.extension,
+                soulbound: self.is_soulbound(deps.storage, &token_id)?,
+            },
+            token_id,
+        })
+    }
+
+    pub fn nft_infos(
+        &self,
+        deps: Deps,
+        token_ids: Vec<String>,
+    ) -> StdResult<TokensWithInfoResponse<T>> {
+        if token_ids.len() > MAX_LIMIT as usize {
+            return Err(StdError::generic_err(format!(
+                "At most {} token ids can be queried at once",
+                MAX_LIMIT
+            )));
+        }
+        let tokens = token_ids
+            .into_iter()
+            .map(|token_id| {
+                let info = self.tokens.load(deps.storage, &token_id)?;
+                self.token_with_info(deps, token_id, info)
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(TokensWithInfoResponse { tokens })
+    }
+
+    pub fn tokens_with_info(
+        &self,
+        deps: Deps,
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<TokensWithInfoResponse<T>> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start = start_after.map(Bound::exclusive);
+
+        let owner_addr = deps.api.addr_validate(&owner)?;
+        let tokens = self
+            .tokens
+            .idx
+            .owner
+            .prefix(owner_addr)
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| {
+                let (token_id, info) = item?;
+                self.token_with_info(deps, token_id.to_string(), info)
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(TokensWithInfoResponse { tokens })
+    }
+
+    pub fn all_tokens_with_info(
+        &self,
+        deps: Deps,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<TokensWithInfoResponse<T>> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start = start_after.map(Bound::exclusive);
+
+        let tokens = self
+            .tokens
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| {
+                let (token_id, info) = item?;
+                self.token_with_info(deps, token_id, info)
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(TokensWithInfoResponse { tokens })
+    }
+
+    pub fn tokens_by_trait(
+        &self,
+        deps: Deps,
+        trait_type: String,
+        value: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<TokensResponse> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start = start_after.map(Bound::exclusive);
+
+        let tokens = self
+            .trait_tokens
+            .prefix((&trait_type, &value))
+            .keys(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(TokensResponse { tokens })
+    }
+
+    pub fn trait_counts(
+        &self,
+        deps: Deps,
+        start_after: Option<(String, String)>,
+        limit: Option<u32>,
+    ) -> StdResult<TraitCountsResponse> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start = start_after.map(|(trait_type, value)| {
+            Bound::exclusive((trait_type.as_str(), value.as_str()).joined_key())
+        });
+
+        let counts = self
+            .trait_counts
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| {
+                let ((trait_type, value), count) = item?;
+                Ok(TraitCount {
+                    trait_type,
+                    value,
+                    count,
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(TraitCountsResponse { counts })
+    }
+
+    pub fn transfer_policy(&self, deps: Deps) -> StdResult<TransferPolicyResponse> {
+        Ok(TransferPolicyResponse {
+            policy: self.transfer_policy.may_load(deps.storage)?,
+        })
+    }
+
     pub fn query(&self, deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
         match msg {
             QueryMsg::Minter {} => to_binary(&self.minter(deps)?),
+            QueryMsg::Frozen { token_id } => to_binary(&self.frozen(deps, token_id)?),
+            QueryMsg::MetadataFrozen {} => to_binary(&FrozenResponse {
+                frozen: self.is_metadata_frozen(deps.storage)?,
+            }),
+            QueryMsg::Provenance {} => to_binary(&self.provenance(deps)?),
+            QueryMsg::TransferPolicy {} => to_binary(&self.transfer_policy(deps)?),
+            QueryMsg::TokensByTrait {
+                trait_type,
+                value,
+                start_after,
+                limit,
+            } => to_binary(&self.tokens_by_trait(deps, trait_type, value, start_after, limit)?),
+            QueryMsg::TraitCounts { start_after, limit } => {
+                to_binary(&self.trait_counts(deps, start_after, limit)?)
+            }
             QueryMsg::ContractInfo {} => to_binary(&self.contract_info(deps)?),
-            QueryMsg::NftInfo { token_id } => to_binary(&self.nft_info(deps, token_id)?),
+            QueryMsg::NftInfo { token_id } => {
+                to_binary(&self.nft_info_with_soulbound(deps, token_id)?)
+            }
             QueryMsg::OwnerOf {
                 token_id,
                 include_expired,
@@ -250,6 +443,15 @@ where
             QueryMsg::AllTokens { start_after, limit } => {
                 to_binary(&self.all_tokens(deps, start_after, limit)?)
             }
+            QueryMsg::NftInfos { token_ids } => to_binary(&self.nft_infos(deps, token_ids)?),
+            QueryMsg::TokensWithInfo {
+                owner,
+                start_after,
+                limit,
+            } => to_binary(&self.tokens_with_info(deps, owner, start_after, limit)?),
+            QueryMsg::AllTokensWithInfo { start_after, limit } => {
+                to_binary(&self.all_tokens_with_info(deps, start_after, limit)?)
+            }
             QueryMsg::Approval {
                 token_id,
                 spender,
@@ -8,6 +8,8 @@ use cosmwasm_std::{Addr, BlockInfo, StdResult, Storage};
 use cw721::{ContractInfoResponse, CustomMsg, Cw721, Expiration};
 use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex};
 
+use crate::msg::TransferPolicy;
+
 pub struct Cw721Contract<'a, T, C>
 where
     T: Serialize + DeserializeOwned + Clone,
@@ -18,6 +20,26 @@ where
     /// Stored as (granter, operator) giving operator full control over granter's account
     pub operators: Map<'a, (&'a Addr, &'a Addr), Expiration>,
     pub tokens: IndexedMap<'a, &'a str, TokenInfo<T>, TokenIndexes<'a, T>>,
+    /// Blocks metadata edits of every token while set
+    pub frozen: Item<'a, bool>,
+    /// Token ids whose metadata can not be edited
+    pub frozen_tokens: Map<'a, &'a str, bool>,
+    /// Hash of all token uris, set once by `FreezeMetadata` which permanently freezes the contract
+    pub provenance: Item<'a, String>,
+    /// Tokens hashed so far while `FreezeMetadata` runs over several calls
+    pub freeze_progress: Item<'a, FreezeProgress>,
+    /// Restrictions on transfers set by the minter, tokens move freely while unset
+    pub transfer_policy: Item<'a, TransferPolicy>,
+    /// Makes every token non-transferable, set on instantiation
+    pub soulbound: Item<'a, bool>,
+    /// Token ids which can not be transferred or approved
+    pub soulbound_tokens: Map<'a, &'a str, bool>,
+    /// Whether `trait_tokens` and `trait_counts` are maintained, set on instantiation
+    pub index_traits: Item<'a, bool>,
+    /// Stored as (trait_type, value, token_id) for every attribute in the token metadata
+    pub trait_tokens: Map<'a, (&'a str, &'a str, &'a str), bool>,
+    /// Number of tokens having each (trait_type, value)
+    pub trait_counts: Map<'a, (&'a str, &'a str), u64>,
 
     pub(crate) _custom_response: PhantomData<C>,
 }
@@ -42,6 +64,16 @@ where
             "operators",
             "tokens",
             "tokens__owner",
+            "frozen",
+            "frozen_tokens",
+            "provenance",
+            "freeze_progress",
+            "transfer_policy",
+            "soulbound",
+            "soulbound_tokens",
+            "index_traits",
+            "trait_tokens",
+            "trait_counts",
         )
     }
 }
@@ -50,6 +82,7 @@ impl<'a, T, C> Cw721Contract<'a, T, C>
 where
     T: Serialize + DeserializeOwned + Clone,
 {
+    #[allow(clippy::too_many_arguments)]
     fn new(
         contract_key: &'a str,
         minter_key: &'a str,
@@ -57,6 +90,16 @@ where
         operator_key: &'a str,
         tokens_key: &'a str,
         tokens_owner_key: &'a str,
+        frozen_key: &'a str,
+        frozen_tokens_key: &'a str,
+        provenance_key: &'a str,
+        freeze_progress_key: &'a str,
+        transfer_policy_key: &'a str,
+        soulbound_key: &'a str,
+        soulbound_tokens_key: &'a str,
+        index_traits_key: &'a str,
+        trait_tokens_key: &'a str,
+        trait_counts_key: &'a str,
     ) -> Self {
         let indexes = TokenIndexes {
             owner: MultiIndex::new(token_owner_idx, tokens_key, tokens_owner_key),
@@ -67,6 +110,16 @@ where
             token_count: Item::new(token_count_key),
             operators: Map::new(operator_key),
             tokens: IndexedMap::new(tokens_key, indexes),
+            frozen: Item::new(frozen_key),
+            frozen_tokens: Map::new(frozen_tokens_key),
+            provenance: Item::new(provenance_key),
+            freeze_progress: Item::new(freeze_progress_key),
+            transfer_policy: Item::new(transfer_policy_key),
+            soulbound: Item::new(soulbound_key),
+            soulbound_tokens: Map::new(soulbound_tokens_key),
+            index_traits: Item::new(index_traits_key),
+            trait_tokens: Map::new(trait_tokens_key),
+            trait_counts: Map::new(trait_counts_key),
             _custom_response: PhantomData,
         }
     }
@@ -81,6 +134,23 @@ where
         Ok(val)
     }
 
+    /// Also true while the freeze is still hashing tokens
+    pub fn is_metadata_frozen(&self, storage: &dyn Storage) -> StdResult<bool> {
+        Ok(self.provenance.may_load(storage)?.is_some()
+            || self.freeze_progress.may_load(storage)?.is_some())
+    }
+
+    pub fn is_frozen(&self, storage: &dyn Storage, token_id: &str) -> StdResult<bool> {
+        Ok(self.is_metadata_frozen(storage)?
+            || self.frozen.may_load(storage)?.unwrap_or_default()
+            || self.frozen_tokens.has(storage, token_id))
+    }
+
+    pub fn is_soulbound(&self, storage: &dyn Storage, token_id: &str) -> StdResult<bool> {
+        Ok(self.soulbound.may_load(storage)?.unwrap_or_default()
+            || self.soulbound_tokens.has(storage, token_id))
+    }
+
     pub fn decrement_tokens(&self, storage: &mut dyn Storage) -> StdResult<u64> {
         let val = self.token_count(storage)? - 1;
         self.token_count.save(storage, &val)?;
@@ -104,6 +174,14 @@ pub struct TokenInfo<T> {
     pub extension: T,
 }
 
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct FreezeProgress {
+    /// Last token hashed, the next call continues after it
+    pub last_token_id: String,
+    /// Hex encoded hash chained over the tokens hashed so far
+    pub hash: String,
+}
+
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct Approval {
     /// Account that can transfer/send the token
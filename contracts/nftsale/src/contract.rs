@@ -20,6 +20,7 @@ use cw721_base::{
     msg::ExecuteMsg as Cw721ExecuteMsg, Extension
 };
 use crate::msg::{ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg, MigrateMsg, };
+use marble_roles::{Role, HasRoleResponse};
 
 use cw20::{ Balance};
 
@@ -60,6 +61,7 @@ pub fn instantiate(
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::GetConfig {} => to_binary(&query_config(deps)?),
+        QueryMsg::HasRole {role, address} => to_binary(&HasRoleResponse { has_role: marble_roles::has_role(deps.storage, &CONFIG, &address, &role)? }),
         QueryMsg::GetToken {index} => to_binary(&query_get_token(deps, index)?),
     }
 }
@@ -95,7 +97,9 @@ pub fn execute(
     match msg {
         ExecuteMsg::UpdateOwner { owner } => util::execute_update_owner(deps.storage, info.sender, owner),
         ExecuteMsg::UpdateEnabled { enabled } => util::execute_update_enabled(deps.storage, info.sender, enabled),
-        ExecuteMsg::SetToken {token_id} => execute_set_token(deps, token_id),
+        ExecuteMsg::GrantRole { role, address } => Ok(marble_roles::execute_grant_role(deps.storage, &CONFIG, &info.sender, role, address)?),
+        ExecuteMsg::RevokeRole { role, address } => Ok(marble_roles::execute_revoke_role(deps.storage, &CONFIG, &info.sender, role, address)?),
+        ExecuteMsg::SetToken {token_id} => execute_set_token(deps, info, token_id),
         ExecuteMsg::Buy { } => execute_buy(deps, env, info),
         ExecuteMsg::Withdraw { index } => execute_withdraw(deps, env, info, index),
         ExecuteMsg::WithdrawId { token_id } => execute_withdraw_id(deps, env, info, token_id),
@@ -104,9 +108,11 @@ pub fn execute(
 
 pub fn execute_set_token(
     deps: DepsMut,
+    info: MessageInfo,
     token_id: String
 ) -> Result<Response, crate::ContractError> {
     util::check_enabled(deps.storage)?;
+    marble_roles::check_role(deps.storage, &CONFIG, &info.sender, Role::SaleModerator)?;
     let mut config = CONFIG.load(deps.storage)?;
     
     TOKENS.save(deps.storage, config.total_count, &token_id)?;
@@ -169,7 +175,7 @@ pub fn execute_withdraw(
     index: u32
 ) -> Result<Response, crate::ContractError> {
 
-    util::check_owner(deps.storage, info.sender.clone())?;
+    marble_roles::check_role(deps.storage, &CONFIG, &info.sender, Role::SaleModerator)?;
     let token_id = TOKENS.load(deps.storage, index)?;
     let config = CONFIG.load(deps.storage)?;
 
@@ -199,7 +205,7 @@ pub fn execute_withdraw_id(
     token_id: String
 ) -> Result<Response, crate::ContractError> {
 
-    util::check_owner(deps.storage, info.sender.clone())?;
+    marble_roles::check_role(deps.storage, &CONFIG, &info.sender, Role::SaleModerator)?;
     let config = CONFIG.load(deps.storage)?;
 
     let mut messages:Vec<CosmosMsg> = vec![];
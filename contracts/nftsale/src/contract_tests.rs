@@ -0,0 +1,149 @@
+#![cfg(test)]
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+use cosmwasm_std::{from_json, Addr, Deps, DepsMut, Response, Uint128};
+use marble_roles::{HasRoleResponse, Role};
+
+use crate::contract::{execute, instantiate, query};
+use crate::msg::{ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg};
+use crate::ContractError;
+
+const OWNER: &str = "owner";
+const MODERATOR: &str = "moderator";
+
+fn setup(deps: DepsMut) {
+    instantiate(
+        deps,
+        mock_env(),
+        mock_info(OWNER, &[]),
+        InstantiateMsg {
+            price: Uint128::from(100u128),
+            denom: "ujuno".to_string(),
+            cw721_address: Addr::unchecked("cw721"),
+        },
+    )
+    .unwrap();
+}
+
+fn run(deps: DepsMut, sender: &str, msg: ExecuteMsg) -> Result<Response, ContractError> {
+    execute(deps, mock_env(), mock_info(sender, &[]), msg)
+}
+
+fn set_role(deps: DepsMut, role: Role, granted: bool) {
+    let address = Addr::unchecked(MODERATOR);
+    let msg = if granted {
+        ExecuteMsg::GrantRole { role, address }
+    } else {
+        ExecuteMsg::RevokeRole { role, address }
+    };
+    run(deps, OWNER, msg).unwrap();
+}
+
+fn has_role(deps: Deps) -> bool {
+    let res: HasRoleResponse = from_json(
+        query(
+            deps,
+            mock_env(),
+            QueryMsg::HasRole {
+                role: Role::SaleModerator,
+                address: Addr::unchecked(MODERATOR),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    res.has_role
+}
+
+#[test]
+fn only_the_owner_grants_roles() {
+    let mut deps = mock_dependencies();
+    setup(deps.as_mut());
+
+    let err = run(
+        deps.as_mut(),
+        MODERATOR,
+        ExecuteMsg::GrantRole {
+            role: Role::SaleModerator,
+            address: Addr::unchecked(MODERATOR),
+        },
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+
+    assert!(!has_role(deps.as_ref()));
+    set_role(deps.as_mut(), Role::SaleModerator, true);
+    assert!(has_role(deps.as_ref()));
+    set_role(deps.as_mut(), Role::SaleModerator, false);
+    assert!(!has_role(deps.as_ref()));
+}
+
+#[test]
+fn sale_moderator_manages_tokens() {
+    let mut deps = mock_dependencies();
+    setup(deps.as_mut());
+
+    let msgs = || {
+        vec![
+            ExecuteMsg::SetToken {
+                token_id: "1".to_string(),
+            },
+            ExecuteMsg::Withdraw { index: 0 },
+            ExecuteMsg::WithdrawId {
+                token_id: "1".to_string(),
+            },
+        ]
+    };
+    run(
+        deps.as_mut(),
+        OWNER,
+        ExecuteMsg::SetToken {
+            token_id: "0".to_string(),
+        },
+    )
+    .unwrap();
+
+    for msg in msgs() {
+        let err = run(deps.as_mut(), MODERATOR, msg).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+    }
+
+    set_role(deps.as_mut(), Role::Pauser, true);
+    for msg in msgs() {
+        let err = run(deps.as_mut(), MODERATOR, msg).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+    }
+
+    set_role(deps.as_mut(), Role::SaleModerator, true);
+    for msg in msgs() {
+        run(deps.as_mut(), MODERATOR, msg).unwrap();
+    }
+    let config: ConfigResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::GetConfig {}).unwrap()).unwrap();
+    assert_eq!(config.total_count, 2);
+
+    set_role(deps.as_mut(), Role::SaleModerator, false);
+    for msg in msgs() {
+        let err = run(deps.as_mut(), MODERATOR, msg).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+    }
+}
+
+#[test]
+fn pauser_updates_enabled() {
+    let mut deps = mock_dependencies();
+    setup(deps.as_mut());
+    let disable = || ExecuteMsg::UpdateEnabled { enabled: false };
+
+    let err = run(deps.as_mut(), MODERATOR, disable()).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+
+    set_role(deps.as_mut(), Role::Pauser, true);
+    run(deps.as_mut(), MODERATOR, disable()).unwrap();
+    let config: ConfigResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::GetConfig {}).unwrap()).unwrap();
+    assert!(!config.enabled);
+
+    set_role(deps.as_mut(), Role::Pauser, false);
+    let err = run(deps.as_mut(), MODERATOR, disable()).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+}
@@ -1,5 +1,6 @@
 use cosmwasm_std::{StdError, Uint128};
 use thiserror::Error;
+use marble_roles::RoleError;
 use cw_utils::{Expiration, Scheduled};
 
 #[derive(Error, Debug)]
@@ -156,3 +157,12 @@ pub enum ContractError {
     #[error("InsufficientRoyalty")]
     InsufficientRoyalty {},
 }
+
+impl From<RoleError> for ContractError {
+    fn from(err: RoleError) -> Self {
+        match err {
+            RoleError::Std(err) => ContractError::Std(err),
+            RoleError::Unauthorized {} => ContractError::Unauthorized {}
+        }
+    }
+}
@@ -7,6 +7,7 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use cw_utils::{Expiration, Scheduled};
 use cw20::Denom;
+use marble_roles::Role;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
@@ -24,6 +25,15 @@ pub enum ExecuteMsg {
     UpdateEnabled {
         enabled: bool
     },
+    /// Lets address act in the given role, only the owner can grant and revoke roles
+    GrantRole {
+        role: Role,
+        address: Addr
+    },
+    RevokeRole {
+        role: Role,
+        address: Addr
+    },
     SetToken {
         token_id: String
     },
@@ -43,6 +53,11 @@ pub enum QueryMsg {
     GetConfig {},
     GetToken {
         index: u32
+    },
+    /// The owner holds every role
+    HasRole {
+        role: Role,
+        address: Addr
     }
 }
 
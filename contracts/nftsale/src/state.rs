@@ -16,6 +16,12 @@ pub struct Config {
     pub denom: String
 }
 
+impl marble_roles::Owned for Config {
+    fn owner(&self) -> &Addr {
+        &self.owner
+    }
+}
+
 pub const CONFIG_KEY: &str = "config";
 pub const CONFIG: Item<Config> = Item::new(CONFIG_KEY);
 
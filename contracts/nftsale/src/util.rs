@@ -5,6 +5,7 @@ use cosmwasm_std::{
 use cw20::{Balance, Cw20ExecuteMsg, Denom, BalanceResponse as CW20BalanceResponse, Cw20QueryMsg};
 use crate::error::ContractError;
 use crate::state::CONFIG;
+use marble_roles::Role;
 use wasmswap::msg::{ExecuteMsg as WasmswapExecuteMsg, QueryMsg as WasmswapQueryMsg, Token1ForToken2PriceResponse, Token2ForToken1PriceResponse, InfoResponse as WasmswapInfoResponse, TokenSelect};
 
 pub const MAX_LIMIT: u32 = 30;
@@ -57,8 +58,7 @@ pub fn execute_update_enabled (
     address: Addr,
     enabled: bool
 ) -> Result<Response, ContractError> {
-    // authorize owner
-    check_owner(storage, address)?;
+    marble_roles::check_role(storage, &CONFIG, &address, Role::Pauser)?;
     
     CONFIG.update(storage, |mut exists| -> StdResult<_> {
         exists.enabled = enabled;
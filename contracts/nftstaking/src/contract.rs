@@ -28,6 +28,7 @@ use sha2::Digest;
 use std::convert::TryInto;
 
 use crate::util;
+use marble_roles::HasRoleResponse;
 use marble_collection::msg::{InstantiateMsg as CollectionInstantiateMsg, ExecuteMsg as CollectionExecuteMsg, QueryMsg as CollectionQueryMsg, ConfigResponse as CollectionConfigResponse};
 
 // version info for migration info
@@ -71,6 +72,8 @@ pub fn execute(
     match msg {
         ExecuteMsg::UpdateOwner { owner } => util::execute_update_owner(deps.storage, info.sender, owner),
         ExecuteMsg::UpdateEnabled { enabled } => util::execute_update_enabled(deps.storage, info.sender, enabled),
+        ExecuteMsg::GrantRole { role, address } => Ok(marble_roles::execute_grant_role(deps.storage, &CONFIG, &info.sender, role, address)?),
+        ExecuteMsg::RevokeRole { role, address } => Ok(marble_roles::execute_revoke_role(deps.storage, &CONFIG, &info.sender, role, address)?),
         ExecuteMsg::UpdateConfig { cw20_address, daily_reward, interval, lock_time } => execute_update_config(deps.storage, info.sender, cw20_address, daily_reward, interval, lock_time),
         ExecuteMsg::ReceiveNft(msg) => execute_receive_nft(deps, env, info, msg),
         ExecuteMsg::Claim { } => execute_claim(deps, env, info),
@@ -332,6 +335,7 @@ pub fn execute_withdraw_id(
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::GetConfig {} => to_binary(&query_config(deps)?),
+        QueryMsg::HasRole {role, address} => to_binary(&HasRoleResponse { has_role: marble_roles::has_role(deps.storage, &CONFIG, &address, &role)? }),
         QueryMsg::GetStaking { address} => to_binary(&query_get_staking(deps, env, address)?),
     }
 }
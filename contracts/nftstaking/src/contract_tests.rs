@@ -0,0 +1,104 @@
+#![cfg(test)]
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+use cosmwasm_std::{from_json, Addr, Deps, DepsMut, Response, Uint128};
+use marble_roles::{HasRoleResponse, Role};
+
+use crate::contract::{execute, instantiate, query};
+use crate::msg::{ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg};
+use crate::ContractError;
+
+const OWNER: &str = "owner";
+const PAUSER: &str = "pauser";
+
+fn setup(deps: DepsMut) {
+    instantiate(
+        deps,
+        mock_env(),
+        mock_info(OWNER, &[]),
+        InstantiateMsg {
+            collection_address: Addr::unchecked("collection"),
+            cw20_address: Addr::unchecked("cw20"),
+            daily_reward: Uint128::from(100u128),
+            interval: 86400,
+            lock_time: 86400,
+        },
+    )
+    .unwrap();
+}
+
+fn run(deps: DepsMut, sender: &str, msg: ExecuteMsg) -> Result<Response, ContractError> {
+    execute(deps, mock_env(), mock_info(sender, &[]), msg)
+}
+
+fn has_role(deps: Deps) -> bool {
+    let res: HasRoleResponse = from_json(
+        query(
+            deps,
+            mock_env(),
+            QueryMsg::HasRole {
+                role: Role::Pauser,
+                address: Addr::unchecked(PAUSER),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    res.has_role
+}
+
+fn enabled(deps: Deps) -> bool {
+    let config: ConfigResponse =
+        from_json(query(deps, mock_env(), QueryMsg::GetConfig {}).unwrap()).unwrap();
+    config.enabled
+}
+
+#[test]
+fn pauser_role_covers_update_enabled() {
+    let mut deps = mock_dependencies();
+    setup(deps.as_mut());
+    let grant = || ExecuteMsg::GrantRole {
+        role: Role::Pauser,
+        address: Addr::unchecked(PAUSER),
+    };
+    let disable = || ExecuteMsg::UpdateEnabled { enabled: false };
+
+    let err = run(deps.as_mut(), PAUSER, grant()).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    let err = run(deps.as_mut(), PAUSER, disable()).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+
+    run(deps.as_mut(), OWNER, grant()).unwrap();
+    assert!(has_role(deps.as_ref()));
+    run(deps.as_mut(), PAUSER, disable()).unwrap();
+    assert!(!enabled(deps.as_ref()));
+
+    // the role does not reach owner-only settings
+    let err = run(
+        deps.as_mut(),
+        PAUSER,
+        ExecuteMsg::UpdateOwner {
+            owner: Addr::unchecked(PAUSER),
+        },
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+
+    run(
+        deps.as_mut(),
+        OWNER,
+        ExecuteMsg::RevokeRole {
+            role: Role::Pauser,
+            address: Addr::unchecked(PAUSER),
+        },
+    )
+    .unwrap();
+    assert!(!has_role(deps.as_ref()));
+    let err = run(
+        deps.as_mut(),
+        PAUSER,
+        ExecuteMsg::UpdateEnabled { enabled: true },
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    assert!(!enabled(deps.as_ref()));
+}
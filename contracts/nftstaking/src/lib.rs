@@ -1,4 +1,5 @@
 pub mod contract;
+mod contract_tests;
 mod error;
 pub mod msg;
 pub mod state;
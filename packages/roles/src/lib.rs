@@ -0,0 +1,123 @@
+use cosmwasm_std::{Addr, Response, StdError, StdResult, Storage};
+use cw_storage_plus::{Item, Map};
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Privileged roles next to the owner of the collection, nftsale and nftstaking contracts
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// Minting, supply, airdrops and token metadata
+    Minter,
+    /// Royalties and the mint payout address
+    RoyaltyManager,
+    /// UpdateEnabled and SetPaused
+    Pauser,
+    /// Supported denoms, delisting and unwinding sales
+    SaleModerator
+}
+
+impl Role {
+    pub fn key(&self) -> &'static str {
+        match self {
+            Role::Minter => "minter",
+            Role::RoyaltyManager => "royalty_manager",
+            Role::Pauser => "pauser",
+            Role::SaleModerator => "sale_moderator"
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HasRoleResponse {
+    pub has_role: bool
+}
+
+// (role key, address) for every role granted by the owner
+pub const ROLES_KEY: &str = "roles";
+pub const ROLES: Map<(&str, &Addr), bool> = Map::new(ROLES_KEY);
+
+/// Turned into the Unauthorized and Std errors of each contract
+#[derive(Error, Debug, PartialEq)]
+pub enum RoleError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+}
+
+/// The contract config whose owner grants the roles
+pub trait Owned {
+    fn owner(&self) -> &Addr;
+}
+
+/// The owner holds every role, anyone else needs it granted
+pub fn has_role<C: Owned + Serialize + DeserializeOwned>(
+    storage: &dyn Storage,
+    config: &Item<C>,
+    address: &Addr,
+    role: &Role
+) -> StdResult<bool> {
+    let cfg = config.load(storage)?;
+    Ok(address == cfg.owner() || ROLES.has(storage, (role.key(), address)))
+}
+
+pub fn check_role<C: Owned + Serialize + DeserializeOwned>(
+    storage: &dyn Storage,
+    config: &Item<C>,
+    address: &Addr,
+    role: Role
+) -> Result<Response, RoleError> {
+    if !has_role(storage, config, address, &role)? {
+        return Err(RoleError::Unauthorized {});
+    }
+    Ok(Response::new().add_attribute("action", "check_role"))
+}
+
+fn check_owner<C: Owned + Serialize + DeserializeOwned>(
+    storage: &dyn Storage,
+    config: &Item<C>,
+    address: &Addr
+) -> Result<(), RoleError> {
+    if address != config.load(storage)?.owner() {
+        return Err(RoleError::Unauthorized {});
+    }
+    Ok(())
+}
+
+/// Only the owner grants roles
+pub fn execute_grant_role<C: Owned + Serialize + DeserializeOwned>(
+    storage: &mut dyn Storage,
+    config: &Item<C>,
+    sender: &Addr,
+    role: Role,
+    address: Addr
+) -> Result<Response, RoleError> {
+    check_owner(storage, config, sender)?;
+    ROLES.save(storage, (role.key(), &address), &true)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "grant_role")
+        .add_attribute("role", role.key())
+        .add_attribute("address", address))
+}
+
+/// Only the owner revokes roles
+pub fn execute_revoke_role<C: Owned + Serialize + DeserializeOwned>(
+    storage: &mut dyn Storage,
+    config: &Item<C>,
+    sender: &Addr,
+    role: Role,
+    address: Addr
+) -> Result<Response, RoleError> {
+    check_owner(storage, config, sender)?;
+    ROLES.remove(storage, (role.key(), &address));
+
+    Ok(Response::new()
+        .add_attribute("action", "revoke_role")
+        .add_attribute("role", role.key())
+        .add_attribute("address", address))
+}